@@ -0,0 +1,132 @@
+//! Native decode throughput over the repository's assets and synthetic grids, run with
+//! `cargo bench`. Throughput is reported in bitstream bytes for the assets and in vertices
+//! for the grids, so sizes and quantization levels can be compared.
+
+use std::fs;
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use draco_decoder::{
+    AttributeDataType, DecodeLimits, DracoDecodeConfig, DracoDecoder, VertexLayout,
+    decode_mesh_auto, decode_mesh_into, decode_point_cloud_auto, try_decode_mesh,
+};
+use tokio::runtime::Runtime;
+
+mod support;
+
+use support::{grid_config, grid_mesh};
+
+/// Grid sides, from about 4 thousand to 1 million vertices.
+const GRID_SIDES: [u32; 3] = [64, 256, 1024];
+/// Raw floats and three quantization levels.
+const QUANTIZATION_BITS: [Option<u8>; 4] = [None, Some(11), Some(14), Some(16)];
+
+fn read_asset(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|err| panic!("failed to read {path}: {err}"))
+}
+
+fn assets(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("assets");
+
+    let mut config = DracoDecodeConfig::new(3254, 4368);
+    config.add_attribute(3, AttributeDataType::Float32);
+    config.add_attribute(3, AttributeDataType::Float32);
+    config.add_attribute(1, AttributeDataType::Float32);
+    let model = [
+        ("20_data", read_asset("assets/20/20_data.bin"), config),
+        (
+            "extracted_model",
+            read_asset("assets/extracted_model/extracted_model_data.bin"),
+            {
+                let mut config = DracoDecodeConfig::new(16744, 54663);
+                config.add_attribute(3, AttributeDataType::Float32);
+                config.add_attribute(2, AttributeDataType::Float32);
+                config
+            },
+        ),
+    ];
+    for (name, data, config) in &model {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                rt.block_on(try_decode_mesh(black_box(data), config))
+                    .unwrap()
+            })
+        });
+    }
+
+    let limits = DecodeLimits::default();
+    let mesh = read_asset("assets/mesh.drc");
+    group.throughput(Throughput::Bytes(mesh.len() as u64));
+    group.bench_function("mesh.drc", |b| {
+        b.iter(|| {
+            rt.block_on(decode_mesh_auto(black_box(&mesh), &limits))
+                .unwrap()
+        })
+    });
+    let point_cloud = read_asset("assets/pointcloud.drc");
+    group.throughput(Throughput::Bytes(point_cloud.len() as u64));
+    group.bench_function("pointcloud.drc", |b| {
+        b.iter(|| decode_point_cloud_auto(black_box(&point_cloud), &limits).unwrap())
+    });
+    group.finish();
+}
+
+fn synthetic(c: &mut Criterion) {
+    let mut group = c.benchmark_group("synthetic");
+    group
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(10));
+    for side in GRID_SIDES {
+        group.throughput(Throughput::Elements((side * side) as u64));
+        let mut decoder = DracoDecoder::new(grid_config(side));
+        for bits in QUANTIZATION_BITS {
+            let data = grid_mesh(side, bits);
+            let level = bits.map_or("f32".to_string(), |bits| format!("q{bits}"));
+            group.bench_with_input(BenchmarkId::new(level, side), &data, |b, data| {
+                b.iter(|| {
+                    decoder
+                        .decode_to_scratch(black_box(data))
+                        .map(<[u8]>::len)
+                        .unwrap()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Planar against interleaved vertices, and a new buffer per decode against decoding into
+/// one that is reused.
+fn output(c: &mut Criterion) {
+    const SIDE: u32 = 256;
+    let rt = Runtime::new().unwrap();
+    let data = grid_mesh(SIDE, Some(14));
+    let mut group = c.benchmark_group("output");
+    group.throughput(Throughput::Elements((SIDE * SIDE) as u64));
+    for layout in [VertexLayout::Planar, VertexLayout::Interleaved] {
+        let mut config = grid_config(SIDE);
+        config.set_vertex_layout(layout);
+        let name = format!("{layout:?}").to_lowercase();
+
+        group.bench_function(BenchmarkId::new("allocating", &name), |b| {
+            b.iter(|| {
+                rt.block_on(try_decode_mesh(black_box(&data), &config))
+                    .unwrap()
+            })
+        });
+        let mut out = vec![0; config.estimate_buffer_size()];
+        group.bench_function(BenchmarkId::new("into", &name), |b| {
+            b.iter(|| {
+                rt.block_on(decode_mesh_into(black_box(&data), &config, &mut out))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, assets, synthetic, output);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+//! Synthetic meshes of any size for the benchmarks. The crate only decodes, so they are
+//! written directly as Draco 2.2 sequential bitstreams: uncompressed connectivity and
+//! either raw or quantized attribute values, which Draco dequantizes while decoding.
+
+use draco_decoder::{AttributeDataType, AttributeSemantic, DracoDecodeConfig};
+
+/// `PREDICTION_NONE` in Draco.
+const PREDICTION_NONE: u8 = 0xfe;
+/// `DT_FLOAT32` in Draco.
+const DRACO_FLOAT32: u8 = 9;
+const SEQUENTIAL_GENERIC: u8 = 0;
+const SEQUENTIAL_QUANTIZATION: u8 = 2;
+
+/// A `side` × `side` grid of vertices over a wavy surface, with positions and texture
+/// coordinates quantized to `quantization_bits`, or stored as raw floats for `None`.
+pub fn grid_mesh(side: u32, quantization_bits: Option<u8>) -> Vec<u8> {
+    let num_points = side * side;
+    let cells = side - 1;
+
+    let mut positions = Vec::with_capacity(num_points as usize * 3);
+    let mut tex_coords = Vec::with_capacity(num_points as usize * 2);
+    for y in 0..side {
+        for x in 0..side {
+            let (u, v) = (x as f32 / cells as f32, y as f32 / cells as f32);
+            let height = (u * 12.0).sin() * (v * 9.0).cos() * 0.1;
+            positions.extend([u * 2.0 - 1.0, height, v * 2.0 - 1.0]);
+            tex_coords.extend([u, v]);
+        }
+    }
+
+    let mut out = b"DRACO".to_vec();
+    // Version 2.2, triangular mesh, sequential encoding, no flags.
+    out.extend([2, 2, 1, 0, 0, 0]);
+    write_varint(&mut out, 2 * cells * cells);
+    write_varint(&mut out, num_points);
+    // Uncompressed connectivity, indices as narrow as the point count allows.
+    out.push(1);
+    for y in 0..cells {
+        for x in 0..cells {
+            let corner = y * side + x;
+            for index in [corner, corner + side, corner + 1].into_iter().chain([
+                corner + 1,
+                corner + side,
+                corner + side + 1,
+            ]) {
+                match num_points {
+                    0..0x100 => out.push(index as u8),
+                    0x100..0x10000 => out.extend((index as u16).to_le_bytes()),
+                    0x10000..0x200000 => write_varint(&mut out, index),
+                    _ => out.extend(index.to_le_bytes()),
+                }
+            }
+        }
+    }
+
+    // A single attributes decoder with the position (unique id 0) and texture
+    // coordinates (unique id 1).
+    out.push(1);
+    write_varint(&mut out, 2);
+    out.extend([0, DRACO_FLOAT32, 3, 0]);
+    write_varint(&mut out, 0);
+    out.extend([3, DRACO_FLOAT32, 2, 0]);
+    write_varint(&mut out, 1);
+    let attributes = [(&positions, 3), (&tex_coords, 2)];
+
+    let Some(bits) = quantization_bits else {
+        out.extend([SEQUENTIAL_GENERIC; 2]);
+        for (values, _) in attributes {
+            out.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+        }
+        return out;
+    };
+
+    // All values come first, then the quantization parameters of each attribute.
+    out.extend([SEQUENTIAL_QUANTIZATION; 2]);
+    let max_quantized = ((1u32 << bits) - 1) as f32;
+    // Quantized values are stored as zigzag encoded symbols, twice the value.
+    let num_bytes = (bits as usize + 1).div_ceil(8);
+    let mut parameters = Vec::new();
+    for (values, dim) in attributes {
+        let mut min = vec![f32::MAX; dim];
+        let mut max = vec![f32::MIN; dim];
+        for vertex in values.chunks(dim) {
+            for (component, &value) in vertex.iter().enumerate() {
+                min[component] = min[component].min(value);
+                max[component] = max[component].max(value);
+            }
+        }
+        let range = (0..dim).map(|c| max[c] - min[c]).fold(0.0, f32::max);
+
+        out.extend([PREDICTION_NONE, 0, num_bytes as u8]);
+        for vertex in values.chunks(dim) {
+            for (component, &value) in vertex.iter().enumerate() {
+                let quantized = ((value - min[component]) / range * max_quantized + 0.5) as u32;
+                out.extend(&(quantized * 2).to_le_bytes()[..num_bytes]);
+            }
+        }
+        parameters.extend(min.iter().flat_map(|value| value.to_le_bytes()));
+        parameters.extend(range.to_le_bytes());
+        parameters.push(bits);
+    }
+    out.extend(parameters);
+    out
+}
+
+/// The config matching [`grid_mesh`] with the same `side`.
+pub fn grid_config(side: u32) -> DracoDecodeConfig {
+    let cells = side - 1;
+    let mut config = DracoDecodeConfig::new(side * side, 6 * cells * cells);
+    config.add_semantic_attribute(AttributeSemantic::Position, 3, AttributeDataType::Float32);
+    config.add_semantic_attribute(
+        AttributeSemantic::TexCoord(0),
+        2,
+        AttributeDataType::Float32,
+    );
+    config
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
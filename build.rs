@@ -1,130 +1,520 @@
-use std::path::Path;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Draco components selected through cargo features.
+struct DracoFeatures {
+    mesh: bool,
+    point_cloud: bool,
+    backwards_compatibility: bool,
+}
+
+impl DracoFeatures {
+    fn from_env() -> Self {
+        let enabled = |feature: &str| env::var(format!("CARGO_FEATURE_{feature}")).is_ok();
+        let mut mesh_only = enabled("MESH_ONLY");
+        let mut point_cloud_only = enabled("POINT_CLOUD_ONLY");
+        if mesh_only && point_cloud_only {
+            // Keep `--all-features` builds working: both together means "everything".
+            println!(
+                "cargo:warning=`mesh-only` and `point-cloud-only` are both enabled, building both decoders"
+            );
+            mesh_only = false;
+            point_cloud_only = false;
+        }
+
+        Self {
+            mesh: !point_cloud_only,
+            point_cloud: !mesh_only,
+            backwards_compatibility: enabled("BACKWARDS_COMPATIBILITY"),
+        }
+    }
+
+    fn cmake_args(&self) -> Vec<String> {
+        let on_off = |enabled: bool| if enabled { "ON" } else { "OFF" };
+        vec![
+            format!("-DDRACO_MESH_COMPRESSION={}", on_off(self.mesh)),
+            format!(
+                "-DDRACO_POINT_CLOUD_COMPRESSION={}",
+                on_off(self.point_cloud)
+            ),
+            format!(
+                "-DDRACO_BACKWARDS_COMPATIBILITY={}",
+                on_off(self.backwards_compatibility)
+            ),
+        ]
+    }
+
+    /// Whether `path`, relative to `third_party/draco/src/draco`, belongs to a decoder that
+    /// is turned off. Shared code such as `PointCloudDecoder`, the base of the mesh
+    /// decoders, is always built.
+    fn excludes(&self, path: &Path) -> bool {
+        let point_cloud_paths = [
+            "compression/point_cloud/algorithms",
+            "compression/point_cloud/point_cloud_kd_tree_decoder.cc",
+            "compression/point_cloud/point_cloud_sequential_decoder.cc",
+            "compression/attributes/kd_tree_attributes_decoder.cc",
+        ];
+        (!self.mesh && path.starts_with("compression/mesh"))
+            || (!self.point_cloud && point_cloud_paths.iter().any(|dir| path.starts_with(dir)))
+    }
+
+    /// Contents of the `draco/draco_features.h` CMake would generate for these options.
+    fn features_header(&self) -> String {
+        let mut defines = Vec::new();
+        if self.mesh {
+            defines.extend([
+                "DRACO_MESH_COMPRESSION_SUPPORTED",
+                "DRACO_NORMAL_ENCODING_SUPPORTED",
+                "DRACO_STANDARD_EDGEBREAKER_SUPPORTED",
+                "DRACO_PREDICTIVE_EDGEBREAKER_SUPPORTED",
+            ]);
+        }
+        if self.point_cloud {
+            defines.push("DRACO_POINT_CLOUD_COMPRESSION_SUPPORTED");
+        }
+        if self.backwards_compatibility {
+            defines.push("DRACO_BACKWARDS_COMPATIBILITY_SUPPORTED");
+        }
+
+        let mut header = String::from("#ifndef DRACO_FEATURES_H_\n#define DRACO_FEATURES_H_\n");
+        for define in defines {
+            header.push_str(&format!("#define {define}\n"));
+        }
+        header.push_str("#endif  // DRACO_FEATURES_H_\n");
+        header
+    }
+}
+
+/// Where the Draco headers and static library were found.
+struct DracoInstall {
+    include_dirs: Vec<PathBuf>,
+    /// `None` when the linker flags were already emitted (pkg-config).
+    lib_dir: Option<PathBuf>,
+    static_link: bool,
+}
+
 fn main() {
-    if std::env::var("DOCS_RS").is_ok() {
+    if env::var("DOCS_RS").is_ok() {
         println!("cargo:warning=Skipping native build on docs.rs");
         return;
     }
 
-    if std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default() == "wasm32" {
+    if env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default() == "wasm32" {
         println!("cargo:warning=Skipping build.rs on wasm32 target");
         return;
     }
 
-    let target = std::env::var("TARGET").unwrap();
+    for var in [
+        "DRACO_DIR",
+        "DRACO_LIB_DIR",
+        "DRACO_INCLUDE_DIR",
+        "DRACO_STATIC",
+        "CMAKE_GENERATOR",
+        "CMAKE_TOOLCHAIN_FILE",
+        "MACOSX_DEPLOYMENT_TARGET",
+        "ANDROID_NDK_HOME",
+        "ANDROID_NDK_ROOT",
+        "ANDROID_PLATFORM",
+    ] {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
 
-    if target.contains("windows-msvc") {
-        let draco_build = "third_party/draco/build";
-        let draco_install = format!("{}/Release", draco_build);
+    let target = env::var("TARGET").unwrap();
 
-        if !Path::new(draco_build).exists() {
-            std::fs::create_dir_all(draco_build).unwrap();
+    let features = DracoFeatures::from_env();
+    let build_vendored = || {
+        if env::var("CARGO_FEATURE_DECODER_ONLY").is_ok() {
+            compile_draco_decoder(&features)
+        } else {
+            build_vendored_draco(&target, &features)
         }
+    };
 
-        // CMake Config
-        let cmake_args = [
-            "..",
-            "-G",
-            "Visual Studio 17 2022",
-            "-A",
-            "x64",
-            "-DBUILD_SHARED_LIBS=OFF",
-            "-DCMAKE_BUILD_TYPE=Release",
-            "-DDRACO_TESTS=OFF",
-            &format!("-DCMAKE_INSTALL_PREFIX={}", draco_install),
-        ];
+    let draco = if env::var("CARGO_FEATURE_SYSTEM_DRACO").is_ok() {
+        find_system_draco().unwrap_or_else(|| {
+            println!("cargo:warning=System Draco not found, building vendored third_party/draco");
+            build_vendored()
+        })
+    } else {
+        build_vendored()
+    };
+
+    // CXX bridge
+    let mut bridge = cxx_build::bridge("src/ffi.rs");
+    bridge
+        .file("cpp/decoder_api.cc")
+        .include("include")
+        .includes(&draco.include_dirs)
+        .flag_if_supported("-std=c++17");
+    if target.contains("apple-darwin") {
+        bridge.flag(format!(
+            "-mmacosx-version-min={}",
+            macos_deployment_target()
+        ));
+    }
+    bridge.compile("decoder_api");
 
-        let status = Command::new("cmake")
-            .args(&cmake_args)
-            .current_dir(draco_build)
-            .status()
-            .expect("Failed to run CMake");
-        assert!(status.success(), "CMake configuration failed");
-
-        // build & install
-        for cmd_args in &[
-            vec!["--build", ".", "--config", "Release"],
-            vec!["--install", ".", "--config", "Release"],
-        ] {
-            let status = Command::new("cmake")
-                .args(cmd_args)
-                .current_dir(draco_build)
-                .status()
-                .expect("Failed to execute cmake");
-            assert!(status.success(), "Draco build/install failed");
+    // Link Draco
+    if let Some(lib_dir) = &draco.lib_dir {
+        println!("cargo:rustc-link-search=native={}", lib_dir.display());
+        if draco.static_link {
+            println!("cargo:rustc-link-lib=static=draco");
+        } else {
+            println!("cargo:rustc-link-lib=draco");
         }
+    }
 
-        // CXX bridge
-        cxx_build::bridge("src/ffi.rs")
-            .file("cpp/decoder_api.cc")
-            .include("include")
-            .include("third_party/draco/src")
-            .include("third_party/draco/build")
-            .include(format!("{draco_install}/include"))
-            .flag_if_supported("-std=c++17")
-            .compile("decoder_api");
-
-        // Link Draco
-        println!("cargo:rustc-link-search=native={}", draco_install);
-        println!("cargo:rustc-link-lib=static=draco");
-
-        println!("cargo:rerun-if-changed=cpp/decoder_api.cc");
-        println!("cargo:rerun-if-changed=include/decoder_api.h");
-        return;
+    println!("cargo:rerun-if-changed=cpp/decoder_api.cc");
+    println!("cargo:rerun-if-changed=include/decoder_api.h");
+}
+
+/// Looks for an installed Draco, in order: `DRACO_LIB_DIR` (+ `DRACO_INCLUDE_DIR`),
+/// `DRACO_DIR` (install prefix or CMake package dir), then pkg-config.
+fn find_system_draco() -> Option<DracoInstall> {
+    let static_link = env::var("DRACO_STATIC").is_ok_and(|v| v != "0");
+
+    if let Ok(lib_dir) = env::var("DRACO_LIB_DIR") {
+        let lib_dir = PathBuf::from(lib_dir);
+        let include_dir = env::var("DRACO_INCLUDE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| lib_dir.join("../include"));
+        return Some(DracoInstall {
+            include_dirs: vec![include_dir],
+            lib_dir: Some(lib_dir),
+            static_link,
+        });
+    }
+
+    if let Ok(draco_dir) = env::var("DRACO_DIR") {
+        // DRACO_DIR may point at the CMake package directory
+        // (<prefix>/lib/cmake/draco or <prefix>/share/cmake/draco) as well as the prefix.
+        let prefix = Path::new(&draco_dir)
+            .ancestors()
+            .find(|dir| dir.join("include/draco").is_dir())
+            .map(Path::to_path_buf);
+        let Some(prefix) = prefix else {
+            println!("cargo:warning=DRACO_DIR={draco_dir} does not contain include/draco");
+            return None;
+        };
+        let lib_dir = ["lib", "lib64"]
+            .iter()
+            .map(|lib| prefix.join(lib))
+            .find(|dir| dir.is_dir())
+            .unwrap_or_else(|| prefix.join("lib"));
+        return Some(DracoInstall {
+            include_dirs: vec![prefix.join("include")],
+            lib_dir: Some(lib_dir),
+            static_link,
+        });
     }
 
-    // Step 1: Build Draco with CMake
-    let draco_dir = "third_party/draco";
-    let draco_build = format!("{draco_dir}/build");
-    let draco_install = format!("{draco_build}/install");
+    probe_pkg_config(static_link)
+}
+
+#[cfg(feature = "system-draco")]
+fn probe_pkg_config(static_link: bool) -> Option<DracoInstall> {
+    let library = pkg_config::Config::new()
+        .statik(static_link)
+        .probe("draco")
+        .ok()?;
+    Some(DracoInstall {
+        include_dirs: library.include_paths,
+        lib_dir: None,
+        static_link,
+    })
+}
+
+#[cfg(not(feature = "system-draco"))]
+fn probe_pkg_config(_static_link: bool) -> Option<DracoInstall> {
+    None
+}
 
-    if !Path::new(&draco_build).exists() {
+fn macos_deployment_target() -> String {
+    env::var("MACOSX_DEPLOYMENT_TARGET").unwrap_or_else(|_| "15.5".to_string())
+}
+
+/// Toolchain file for `target`, looked up like the `cmake` crate does:
+/// `CMAKE_TOOLCHAIN_FILE_<target>`, `CMAKE_TOOLCHAIN_FILE_<target_with_underscores>`,
+/// `TARGET_CMAKE_TOOLCHAIN_FILE` (when cross compiling) and `CMAKE_TOOLCHAIN_FILE`.
+fn cmake_toolchain_file(target: &str, cross: bool) -> Option<String> {
+    let mut vars = vec![
+        format!("CMAKE_TOOLCHAIN_FILE_{target}"),
+        format!("CMAKE_TOOLCHAIN_FILE_{}", target.replace('-', "_")),
+    ];
+    if cross {
+        vars.push("TARGET_CMAKE_TOOLCHAIN_FILE".to_string());
+    }
+    vars.push("CMAKE_TOOLCHAIN_FILE".to_string());
+
+    vars.iter().find_map(|var| {
+        println!("cargo:rerun-if-env-changed={var}");
+        env::var(var).ok()
+    })
+}
+
+fn android_abi(target: &str) -> &'static str {
+    match target.split('-').next().unwrap_or_default() {
+        "aarch64" => "arm64-v8a",
+        "armv7" | "thumbv7neon" | "arm" => "armeabi-v7a",
+        "i686" => "x86",
+        _ => "x86_64",
+    }
+}
+
+fn cmake_system_name(target: &str) -> Option<&'static str> {
+    if target.contains("android") {
+        Some("Android")
+    } else if target.contains("apple-ios") {
+        Some("iOS")
+    } else if target.contains("apple-darwin") {
+        Some("Darwin")
+    } else if target.contains("windows") {
+        Some("Windows")
+    } else if target.contains("linux") {
+        Some("Linux")
+    } else if target.contains("freebsd") {
+        Some("FreeBSD")
+    } else {
+        None
+    }
+}
+
+/// Cache arguments that point CMake at the compiler and target cc-rs would use,
+/// so the vendored Draco is built for `TARGET` rather than the host.
+fn cmake_target_args(target: &str) -> Vec<String> {
+    let host = env::var("HOST").unwrap();
+    let cross = host != target;
+    let arch = target.split('-').next().unwrap_or_default();
+    let mut args = Vec::new();
+
+    let android_ndk = if target.contains("android") {
+        env::var("ANDROID_NDK_HOME")
+            .or_else(|_| env::var("ANDROID_NDK_ROOT"))
+            .ok()
+    } else {
+        None
+    };
+
+    if let Some(toolchain_file) = cmake_toolchain_file(target, cross) {
+        // The toolchain file owns compilers, sysroot and flags.
+        args.push(format!("-DCMAKE_TOOLCHAIN_FILE={toolchain_file}"));
+    } else if let Some(ndk) = android_ndk {
+        let platform = env::var("ANDROID_PLATFORM").unwrap_or_else(|_| "android-21".to_string());
+        args.push(format!(
+            "-DCMAKE_TOOLCHAIN_FILE={ndk}/build/cmake/android.toolchain.cmake"
+        ));
+        args.push(format!("-DANDROID_ABI={}", android_abi(target)));
+        args.push(format!("-DANDROID_PLATFORM={platform}"));
+    } else if !target.contains("msvc") {
+        for (lang, cpp) in [("C", false), ("CXX", true)] {
+            let compiler = cc::Build::new().cpp(cpp).get_compiler();
+            let flags = compiler
+                .args()
+                .iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            args.push(format!(
+                "-DCMAKE_{lang}_COMPILER={}",
+                compiler.path().display()
+            ));
+            args.push(format!("-DCMAKE_{lang}_FLAGS={flags}"));
+        }
+
+        if cross {
+            if let Some(system_name) = cmake_system_name(target) {
+                args.push(format!("-DCMAKE_SYSTEM_NAME={system_name}"));
+            }
+            args.push(format!("-DCMAKE_SYSTEM_PROCESSOR={arch}"));
+        }
+    }
+
+    if target.contains("apple") {
+        let osx_arch = if arch == "aarch64" { "arm64" } else { arch };
+        args.push(format!("-DCMAKE_OSX_ARCHITECTURES={osx_arch}"));
+    }
+    if target.contains("apple-darwin") {
+        args.push(format!(
+            "-DCMAKE_OSX_DEPLOYMENT_TARGET={}",
+            macos_deployment_target()
+        ));
+    }
+    if target.contains("apple-ios") {
+        let sysroot = if target.ends_with("-sim") || arch == "x86_64" {
+            "iphonesimulator"
+        } else {
+            "iphoneos"
+        };
+        args.push(format!("-DCMAKE_OSX_SYSROOT={sysroot}"));
+    }
+
+    args
+}
+
+/// Configures, builds and installs the vendored `third_party/draco` with CMake
+/// into `OUT_DIR`, so builds for different targets don't share a CMake cache.
+fn build_vendored_draco(target: &str, features: &DracoFeatures) -> DracoInstall {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let draco_dir = manifest_dir.join("third_party/draco");
+    let draco_build = out_dir.join("draco/build");
+    let draco_install = out_dir.join("draco/install");
+
+    if !draco_build.exists() {
         std::fs::create_dir_all(&draco_build).unwrap();
     }
 
-    let status = Command::new("cmake")
-        .args([
-            "..",
-            "-G",
-            "Unix Makefiles",
+    let mut cmake_args = vec![draco_dir.display().to_string()];
+    // Let CMake pick its default generator (or CMAKE_GENERATOR) unless we know better.
+    if env::var("CMAKE_GENERATOR").is_err() {
+        if target.contains("windows-msvc") {
+            let platform = match target.split('-').next().unwrap_or_default() {
+                "i686" | "i586" => "Win32",
+                "aarch64" => "ARM64",
+                _ => "x64",
+            };
+            cmake_args.extend(["-G", "Visual Studio 17 2022", "-A", platform].map(String::from));
+        } else {
+            cmake_args.extend(["-G", "Unix Makefiles"].map(String::from));
+        }
+    }
+    cmake_args.extend(
+        [
             "-DBUILD_SHARED_LIBS=OFF",
             "-DCMAKE_BUILD_TYPE=Release",
+            "-DCMAKE_POSITION_INDEPENDENT_CODE=ON",
+            "-DCMAKE_INSTALL_LIBDIR=lib",
             "-DDRACO_TESTS=OFF",
-            &format!("-DCMAKE_INSTALL_PREFIX={}", "install"),
-        ])
+        ]
+        .map(String::from),
+    );
+    cmake_args.push(format!(
+        "-DCMAKE_INSTALL_PREFIX={}",
+        draco_install.display()
+    ));
+    cmake_args.extend(features.cmake_args());
+    cmake_args.extend(cmake_target_args(target));
+
+    let cmake = env::var("CMAKE").unwrap_or_else(|_| "cmake".to_string());
+    println!("cargo:rerun-if-env-changed=CMAKE");
+
+    let status = Command::new(&cmake)
+        .args(&cmake_args)
         .current_dir(&draco_build)
         .status()
         .expect("Failed to run CMake");
     assert!(status.success(), "CMake configuration failed");
 
-    let status = Command::new("cmake")
-        .args(["--build", "."])
+    let jobs = env::var("NUM_JOBS").unwrap_or_else(|_| "1".to_string());
+    let status = Command::new(&cmake)
+        .args(["--build", ".", "--config", "Release", "--parallel", &jobs])
         .current_dir(&draco_build)
         .status()
         .expect("Failed to build Draco");
     assert!(status.success(), "Draco build failed");
 
-    let status = Command::new("cmake")
-        .args(["--install", "."])
+    let status = Command::new(&cmake)
+        .args(["--install", ".", "--config", "Release"])
         .current_dir(&draco_build)
         .status()
         .expect("Failed to install Draco");
     assert!(status.success(), "Draco install failed");
 
-    cxx_build::bridge("src/ffi.rs")
-        .file("cpp/decoder_api.cc")
-        .include("include")
-        .include("third_party/draco/src")
-        .include("third_party/draco/build")
-        .include(format!("{draco_install}/include"))
-        .flag_if_supported("-std=c++17")
-        .flag("-mmacosx-version-min=15.5")
-        .compile("decoder_api");
+    DracoInstall {
+        include_dirs: vec![
+            draco_dir.join("src"),
+            draco_build.clone(),
+            draco_install.join("include"),
+        ],
+        lib_dir: Some(draco_install.join("lib")),
+        static_link: true,
+    }
+}
 
-    println!("cargo:rustc-link-search=native={draco_install}/lib");
-    println!("cargo:rustc-link-lib=static=draco");
+/// Source directories of `third_party/draco/src/draco` the decoder needs.
+const DECODER_SOURCE_DIRS: &[&str] = &[
+    "attributes",
+    "compression",
+    "core",
+    "mesh",
+    "metadata",
+    "point_cloud",
+];
 
-    println!("cargo:rerun-if-changed=cpp/decoder_api.cc");
-    println!("cargo:rerun-if-changed=include/decoder_api.h");
+/// Pushes the decoder sources under `dir`, relative to `root`, that `features` needs.
+fn collect_decoder_sources(
+    root: &Path,
+    dir: &Path,
+    features: &DracoFeatures,
+    sources: &mut Vec<PathBuf>,
+) {
+    for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+        let path = dir.join(entry.unwrap().file_name());
+        if features.excludes(&path) {
+            continue;
+        }
+        if root.join(&path).is_dir() {
+            collect_decoder_sources(root, &path, features, sources);
+            continue;
+        }
+
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let encoder_only = name.contains("encod") && name != "encoder_buffer.cc";
+        if name.ends_with(".cc") && !name.contains("test") && !encoder_only {
+            sources.push(root.join(path));
+        }
+    }
+}
+
+/// Compiles only the decoder sources of `third_party/draco` with `cc`, skipping CMake,
+/// the encoder, the transcoder and the command line tools.
+fn compile_draco_decoder(features: &DracoFeatures) -> DracoInstall {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let draco_src = manifest_dir.join("third_party/draco/src");
+    let generated_include = out_dir.join("draco/include");
+    assert!(
+        draco_src.join("draco").is_dir(),
+        "Draco sources not found in {}, run `git submodule update --init`",
+        draco_src.display()
+    );
+
+    std::fs::create_dir_all(generated_include.join("draco")).unwrap();
+    std::fs::write(
+        generated_include.join("draco/draco_features.h"),
+        features.features_header(),
+    )
+    .unwrap();
+
+    let mut sources = Vec::new();
+    for dir in DECODER_SOURCE_DIRS {
+        collect_decoder_sources(
+            &draco_src.join("draco"),
+            Path::new(dir),
+            features,
+            &mut sources,
+        );
+    }
+    sources.sort();
+
+    cc::Build::new()
+        .cpp(true)
+        .std("c++17")
+        .include(&draco_src)
+        .include(&generated_include)
+        .files(&sources)
+        .warnings(false)
+        .compile("draco");
+
+    println!("cargo:rerun-if-changed={}", draco_src.display());
+
+    DracoInstall {
+        include_dirs: vec![draco_src, generated_include],
+        // cc already emitted the link flags for libdraco.a
+        lib_dir: None,
+        static_link: true,
+    }
 }
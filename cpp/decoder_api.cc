@@ -1,14 +1,28 @@
 #include "decoder_api.h"
 
+#include "draco/draco_features.h"
+
 #include "draco/attributes/geometry_attribute.h"
 #include "draco/attributes/point_attribute.h"
 #include "draco/compression/decode.h"
 #include "draco/compression/mesh/mesh_decoder.h"
+#ifdef DRACO_MESH_COMPRESSION_SUPPORTED
+#include "draco/compression/mesh/mesh_edgebreaker_decoder.h"
+#include "draco/compression/mesh/mesh_sequential_decoder.h"
+#endif
 #include "draco/compression/point_cloud/point_cloud_decoder.h"
+#ifdef DRACO_POINT_CLOUD_COMPRESSION_SUPPORTED
+#include "draco/compression/point_cloud/point_cloud_kd_tree_decoder.h"
+#include "draco/compression/point_cloud/point_cloud_sequential_decoder.h"
+#endif
 #include "draco/core/decoder_buffer.h"
+#include "draco/mesh/corner_table.h"
 #include "draco/mesh/mesh.h"
 #include "draco/point_cloud/point_cloud.h"
 
+#include <algorithm>
+#include <limits>
+
 static size_t sizeof_data_type(draco::DataType type) {
   switch (type) {
   case draco::DT_INT8:
@@ -44,14 +58,19 @@ rust::Vec<uint8_t> decode_point_cloud(rust::Slice<const uint8_t> data) {
 
   const draco::PointAttribute *attr =
       pc->GetNamedAttribute(draco::GeometryAttribute::POSITION);
-  if (!attr) {
+  if (!attr || attr->num_components() > 3) {
     return {};
   }
 
   rust::Vec<uint8_t> out;
+  out.reserve(static_cast<size_t>(pc->num_points()) * 3 * sizeof(float));
   for (draco::PointIndex i(0); i < pc->num_points(); ++i) {
+    const draco::AttributeValueIndex value_index = attr->mapped_index(i);
+    if (value_index.value() >= attr->size()) {
+      return {};
+    }
     float point[3] = {0.0f};
-    attr->GetValue(attr->mapped_index(i), &point[0]);
+    attr->ConvertValue(value_index, &point[0]);
     uint8_t *ptr = reinterpret_cast<uint8_t *>(point);
     for (size_t j = 0; j < sizeof(point); ++j) {
       out.push_back(ptr[j]);
@@ -60,46 +79,236 @@ rust::Vec<uint8_t> decode_point_cloud(rust::Slice<const uint8_t> data) {
   return out;
 }
 
-size_t decode_mesh_direct_write(const uint8_t *data, size_t data_len,
-                                uint8_t *out_ptr, size_t out_len) {
+DracoGeometry::DracoGeometry(std::unique_ptr<draco::PointCloud> point_cloud,
+                             const draco::Mesh *mesh)
+    : point_cloud_(std::move(point_cloud)), mesh_(mesh) {
+  // --------- SORT ATTRIBUTES BY attribute_id (unique_id) ----------
+  attributes_.reserve(point_cloud_->num_attributes());
+  for (int i = 0; i < point_cloud_->num_attributes(); ++i) {
+    attributes_.push_back(point_cloud_->attribute(i));
+  }
+  std::sort(attributes_.begin(), attributes_.end(),
+            [](const draco::PointAttribute *a, const draco::PointAttribute *b) {
+              return a->unique_id() < b->unique_id();
+            });
+}
+
+// Reports the stages of PointCloudDecoder::Decode after the header and
+// metadata to `timer` as they run.
+template <class Base> class TimedDecoder : public Base {
+public:
+  explicit TimedDecoder(StageTimer &timer) : timer_(timer) {}
+
+protected:
+  bool DecodeGeometryData() override {
+    timer_.begin(DecodeStage::Connectivity);
+    const bool ok = Base::DecodeGeometryData();
+    timer_.end(DecodeStage::Connectivity);
+    return ok;
+  }
+
+  // Includes dequantizing, which the attribute decoders do in their
+  // TransformAttributesToOriginalFormat pass, out of reach of this class.
+  bool DecodePointAttributes() override {
+    timer_.begin(DecodeStage::Attributes);
+    const bool ok = Base::DecodePointAttributes();
+    timer_.end(DecodeStage::Attributes);
+    return ok;
+  }
+
+private:
+  StageTimer &timer_;
+};
+
+std::unique_ptr<DracoGeometry> decode_geometry(rust::Slice<const uint8_t> data,
+                                               const DecodeOptions &options,
+                                               StageTimer &timer) {
   draco::DecoderBuffer buffer;
-  buffer.Init(reinterpret_cast<const char *>(data), data_len);
+  buffer.Init(reinterpret_cast<const char *>(data.data()), data.size());
 
-  draco::Decoder decoder;
-  auto status_or_geometry = decoder.DecodeMeshFromBuffer(&buffer);
-  if (!status_or_geometry.ok()) {
-    return 0;
+  draco::DracoHeader header;
+  draco::DecoderBuffer header_buffer(buffer);
+  if (!draco::PointCloudDecoder::DecodeHeader(&header_buffer, &header).ok()) {
+    return nullptr;
   }
 
-  std::unique_ptr<draco::Mesh> mesh = std::move(status_or_geometry).value();
+  draco::DecoderOptions decoder_options;
+  for (int type = 0; type < draco::GeometryAttribute::NAMED_ATTRIBUTES_COUNT;
+       ++type) {
+    if (options.skip_transforms & (1u << type)) {
+      decoder_options.SetAttributeBool(
+          static_cast<draco::GeometryAttribute::Type>(type),
+          "skip_attribute_transform", true);
+    }
+  }
 
-  uint8_t *out = out_ptr;
-  const size_t out_end = reinterpret_cast<size_t>(out_ptr) + out_len;
+  // The decoders are created here instead of by draco::Decoder to time them.
+  if (header.encoder_type == draco::TRIANGULAR_MESH) {
+#ifdef DRACO_MESH_COMPRESSION_SUPPORTED
+    std::unique_ptr<draco::MeshDecoder> decoder;
+    if (header.encoder_method == draco::MESH_EDGEBREAKER_ENCODING) {
+      decoder =
+          std::make_unique<TimedDecoder<draco::MeshEdgebreakerDecoder>>(timer);
+    } else if (header.encoder_method == draco::MESH_SEQUENTIAL_ENCODING) {
+      decoder =
+          std::make_unique<TimedDecoder<draco::MeshSequentialDecoder>>(timer);
+    } else {
+      return nullptr;
+    }
+    auto mesh = std::make_unique<draco::Mesh>();
+    if (!decoder->Decode(decoder_options, &buffer, mesh.get()).ok()) {
+      return nullptr;
+    }
+    const draco::Mesh *mesh_ptr = mesh.get();
+    return std::make_unique<DracoGeometry>(std::move(mesh), mesh_ptr);
+#else
+    return nullptr;
+#endif
+  }
 
-  auto write_scalar = [&](const void *src, draco::DataType type) -> bool {
-    size_t size = sizeof_data_type(type);
-    if (reinterpret_cast<size_t>(out) + size > out_end)
-      return false;
-    memcpy(out, src, size);
-    out += size;
+#ifdef DRACO_POINT_CLOUD_COMPRESSION_SUPPORTED
+  std::unique_ptr<draco::PointCloudDecoder> decoder;
+  if (header.encoder_type != draco::POINT_CLOUD) {
+    return nullptr;
+  } else if (header.encoder_method == draco::POINT_CLOUD_SEQUENTIAL_ENCODING) {
+    decoder = std::make_unique<
+        TimedDecoder<draco::PointCloudSequentialDecoder>>(timer);
+  } else if (header.encoder_method == draco::POINT_CLOUD_KD_TREE_ENCODING) {
+    decoder =
+        std::make_unique<TimedDecoder<draco::PointCloudKdTreeDecoder>>(timer);
+  } else {
+    return nullptr;
+  }
+  auto point_cloud = std::make_unique<draco::PointCloud>();
+  if (!decoder->Decode(decoder_options, &buffer, point_cloud.get()).ok()) {
+    return nullptr;
+  }
+  return std::make_unique<DracoGeometry>(std::move(point_cloud), nullptr);
+#else
+  return nullptr;
+#endif
+}
+
+GeometryInfo geometry_info(const DracoGeometry &geometry) {
+  GeometryInfo info;
+  info.is_mesh = geometry.mesh() != nullptr;
+  info.num_points = geometry.point_cloud().num_points();
+  info.num_faces = info.is_mesh ? geometry.mesh()->num_faces() : 0;
+
+  for (const draco::PointAttribute *attr : geometry.attributes()) {
+    AttributeInfo attr_info;
+    attr_info.unique_id = attr->unique_id();
+    attr_info.attribute_type = static_cast<int32_t>(attr->attribute_type());
+    attr_info.data_type = static_cast<int32_t>(attr->data_type());
+    attr_info.num_components = attr->num_components();
+    attr_info.normalized = attr->normalized();
+    info.attributes.push_back(attr_info);
+  }
+  return info;
+}
+
+// Writes the value of `attr` for each of `num_values` points, converted to T,
+// to `out_ptr + output.offset` with consecutive values `output.stride` bytes
+// apart, accumulating the component-wise min, max and sum of the written values
+// into `stats`. Value j is the value of `points[j]`, or of point j when
+// `points` is null.
+template <typename T>
+static bool write_attribute(const draco::PointAttribute *attr,
+                            const draco::PointIndex *points,
+                            uint32_t num_values, uint8_t *out_ptr,
+                            size_t out_len, const AttributeOutput &output,
+                            AttributeStats &stats) {
+  const int dim = attr->num_components();
+  // Draco converts values through an int8_t component count.
+  if (dim < 1 || dim > std::numeric_limits<int8_t>::max())
+    return false;
+
+  const size_t value_size = sizeof(T) * dim;
+  if (num_values == 0)
     return true;
-  };
+  // offset + (num_values - 1) * stride + value_size must fit in out_len.
+  if (output.stride < value_size || output.offset > out_len ||
+      out_len - output.offset < value_size)
+    return false;
+  const size_t last = static_cast<size_t>(num_values - 1);
+  if (last > (out_len - output.offset - value_size) / output.stride)
+    return false;
+
+  // Sized by the attribute, e.g. 16 for spherical harmonics coefficients.
+  std::vector<double> min(dim, std::numeric_limits<double>::infinity());
+  std::vector<double> max(dim, -std::numeric_limits<double>::infinity());
+  std::vector<double> sum(dim, 0.0);
+  std::vector<T> v(dim);
+  uint8_t *out = out_ptr + output.offset;
+  for (uint32_t j = 0; j < num_values; ++j) {
+    const draco::PointIndex point = points ? points[j] : draco::PointIndex(j);
+    const draco::AttributeValueIndex value_index = attr->mapped_index(point);
+    if (value_index.value() >= attr->size())
+      return false;
+    if (!attr->ConvertValue(value_index, v.data()))
+      return false;
+    memcpy(out, v.data(), value_size);
+    out += output.stride;
+    for (int c = 0; c < dim; ++c) {
+      const double value = static_cast<double>(v[c]);
+      min[c] = std::min(min[c], value);
+      max[c] = std::max(max[c], value);
+      sum[c] += value;
+    }
+  }
 
-  // Write indices (same as before)
-  const int num_faces = mesh->num_faces();
-  const int num_indices = num_faces * 3;
+  for (int c = 0; c < dim; ++c) {
+    stats.min.push_back(min[c]);
+    stats.max.push_back(max[c]);
+    stats.sum.push_back(sum[c]);
+  }
+  return true;
+}
 
-  bool use_u16 =
-      (num_indices <= static_cast<int>(std::numeric_limits<uint16_t>::max()));
+bool write_geometry(const DracoGeometry &geometry, uint8_t *out_ptr,
+                    size_t out_len, rust::Slice<const AttributeOutput> outputs,
+                    bool per_corner, bool wide_indices,
+                    rust::Vec<AttributeStats> &stats) {
+  const draco::Mesh *mesh = geometry.mesh();
+  const uint32_t num_faces = mesh ? mesh->num_faces() : 0;
+  const uint32_t num_points = geometry.point_cloud().num_points();
+  const auto &attrs = geometry.attributes();
+  if (per_corner && !mesh) {
+    return false;
+  }
+  for (const AttributeOutput &output : outputs) {
+    if (output.source >= attrs.size())
+      return false;
+  }
+
+  uint8_t *out = out_ptr;
+  uint8_t *const out_end = out_ptr + out_len;
+
+  // Write indices, don't trust the decoded faces to stay inside the points.
+  // Per corner output gets sequential indices and one value per corner.
+  const size_t num_indices = static_cast<size_t>(num_faces) * 3;
+  std::vector<draco::PointIndex> corners;
+  if (per_corner) {
+    corners.reserve(num_indices);
+  }
+
+  bool use_u16 = !wide_indices &&
+                 num_indices <= std::numeric_limits<uint16_t>::max();
 
   if (use_u16) {
     for (draco::FaceIndex i(0); i < num_faces; ++i) {
       const auto &face = mesh->face(i);
       for (int j = 0; j < 3; ++j) {
+        if (face[j].value() >= num_points)
+          return false;
         uint16_t val = static_cast<uint16_t>(face[j].value());
-        if (reinterpret_cast<size_t>(out) + sizeof(uint16_t) > out_end)
-          return 0;
-        *reinterpret_cast<uint16_t *>(out) = val;
+        if (per_corner) {
+          val = static_cast<uint16_t>(corners.size());
+          corners.push_back(face[j]);
+        }
+        if (static_cast<size_t>(out_end - out) < sizeof(uint16_t))
+          return false;
+        memcpy(out, &val, sizeof(uint16_t));
         out += sizeof(uint16_t);
       }
     }
@@ -107,121 +316,213 @@ size_t decode_mesh_direct_write(const uint8_t *data, size_t data_len,
     for (draco::FaceIndex i(0); i < num_faces; ++i) {
       const auto &face = mesh->face(i);
       for (int j = 0; j < 3; ++j) {
+        if (face[j].value() >= num_points)
+          return false;
         uint32_t val = static_cast<uint32_t>(face[j].value());
-        if (reinterpret_cast<size_t>(out) + sizeof(uint32_t) > out_end)
-          return 0;
-        *reinterpret_cast<uint32_t *>(out) = val;
+        if (per_corner) {
+          val = static_cast<uint32_t>(corners.size());
+          corners.push_back(face[j]);
+        }
+        if (static_cast<size_t>(out_end - out) < sizeof(uint32_t))
+          return false;
+        memcpy(out, &val, sizeof(uint32_t));
         out += sizeof(uint32_t);
       }
     }
   }
 
-  // --------- NEW: SORT ATTRIBUTES BY attribute_id (unique_id) ----------
-  struct AttrEntry {
-    const draco::PointAttribute *attr = nullptr;
-    int attribute_id = 0;
-  };
+  // --------- WRITE ATTRIBUTES ----------
+  // Each output names the attribute it takes, in the sorted order, which is
+  // converted to the data type the caller laid out for it, planar or
+  // interleaved depending on the offsets and strides.
+  const draco::PointIndex *points = per_corner ? corners.data() : nullptr;
+  const uint32_t num_values =
+      per_corner ? static_cast<uint32_t>(corners.size()) : num_points;
+  for (size_t i = 0; i < outputs.size(); ++i) {
+    const AttributeOutput &output = outputs[i];
+    const draco::PointAttribute *attr = attrs[output.source];
+    AttributeStats attr_stats;
+    bool ok = false;
+    switch (static_cast<draco::DataType>(output.data_type)) {
+    case draco::DT_INT8:
+      ok = write_attribute<int8_t>(attr, points, num_values, out_ptr,
+                                   out_len, output, attr_stats);
+      break;
+    case draco::DT_UINT8:
+      ok = write_attribute<uint8_t>(attr, points, num_values, out_ptr,
+                                    out_len, output, attr_stats);
+      break;
+    case draco::DT_INT16:
+      ok = write_attribute<int16_t>(attr, points, num_values, out_ptr,
+                                    out_len, output, attr_stats);
+      break;
+    case draco::DT_UINT16:
+      ok = write_attribute<uint16_t>(attr, points, num_values, out_ptr,
+                                     out_len, output, attr_stats);
+      break;
+    case draco::DT_INT32:
+      ok = write_attribute<int32_t>(attr, points, num_values, out_ptr,
+                                    out_len, output, attr_stats);
+      break;
+    case draco::DT_UINT32:
+      ok = write_attribute<uint32_t>(attr, points, num_values, out_ptr,
+                                     out_len, output, attr_stats);
+      break;
+    case draco::DT_FLOAT32:
+      ok = write_attribute<float>(attr, points, num_values, out_ptr,
+                                  out_len, output, attr_stats);
+      break;
+    case draco::DT_INT64:
+      ok = write_attribute<int64_t>(attr, points, num_values, out_ptr,
+                                    out_len, output, attr_stats);
+      break;
+    case draco::DT_UINT64:
+      ok = write_attribute<uint64_t>(attr, points, num_values, out_ptr,
+                                     out_len, output, attr_stats);
+      break;
+    case draco::DT_FLOAT64:
+      ok = write_attribute<double>(attr, points, num_values, out_ptr,
+                                   out_len, output, attr_stats);
+      break;
+    default:
+      ok = false;
+    }
+    if (!ok)
+      return false;
+    stats.push_back(std::move(attr_stats));
+  }
 
-  std::vector<AttrEntry> attrs;
-  attrs.reserve(mesh->num_attributes());
+  return true;
+}
 
-  for (int i = 0; i < mesh->num_attributes(); ++i) {
-    const draco::PointAttribute *attr = mesh->attribute(i);
+#ifdef DRACO_MESH_COMPRESSION_SUPPORTED
+// Indexes the unique POSITION values instead of the points, so seams in other
+// attributes don't split vertices.
+static bool write_position_values(const draco::Mesh &mesh,
+                                  const draco::PointAttribute *attr,
+                                  rust::Vec<uint32_t> &indices,
+                                  rust::Vec<float> &positions) {
+  const uint32_t num_values = attr->size();
+  indices.reserve(static_cast<size_t>(mesh.num_faces()) * 3);
+  for (draco::FaceIndex i(0); i < mesh.num_faces(); ++i) {
+    const auto &face = mesh.face(i);
+    for (int j = 0; j < 3; ++j) {
+      if (face[j].value() >= mesh.num_points())
+        return false;
+      const draco::AttributeValueIndex value_index =
+          attr->mapped_index(face[j]);
+      if (value_index.value() >= num_values)
+        return false;
+      indices.push_back(value_index.value());
+    }
+  }
 
-    AttrEntry e;
-    e.attr = attr;
-    e.attribute_id = attr->unique_id();
+  positions.reserve(static_cast<size_t>(num_values) * 3);
+  for (draco::AttributeValueIndex i(0); i < num_values; ++i) {
+    float v[3] = {0.0f, 0.0f, 0.0f};
+    if (!attr->ConvertValue(i, v))
+      return false;
+    for (int c = 0; c < 3; ++c) {
+      positions.push_back(v[c]);
+    }
+  }
+  return true;
+}
 
-    attrs.push_back(e);
+// Writes the vertex and opposite corner of every corner of `table`, which
+// matches the faces of `mesh`, and the position of every vertex.
+static bool write_corner_table(const draco::Mesh &mesh,
+                               const draco::PointAttribute *attr,
+                               const draco::CornerTable &table,
+                               rust::Vec<uint32_t> &indices,
+                               rust::Vec<uint32_t> &opposites,
+                               rust::Vec<float> &positions) {
+  const uint32_t num_corners = table.num_corners();
+  const uint32_t num_vertices = table.num_vertices();
+  if (num_corners != static_cast<size_t>(mesh.num_faces()) * 3)
+    return false;
+
+  std::vector<float> vertex_positions(static_cast<size_t>(num_vertices) * 3,
+                                      0.0f);
+  indices.reserve(num_corners);
+  opposites.reserve(num_corners);
+  for (draco::CornerIndex c(0); c < num_corners; ++c) {
+    const draco::VertexIndex vertex = table.Vertex(c);
+    const draco::PointIndex point = mesh.face(table.Face(c))[c.value() % 3];
+    if (vertex.value() >= num_vertices || point.value() >= mesh.num_points())
+      return false;
+    const draco::AttributeValueIndex value_index = attr->mapped_index(point);
+    float *position =
+        &vertex_positions[static_cast<size_t>(vertex.value()) * 3];
+    if (value_index.value() >= attr->size() ||
+        !attr->ConvertValue(value_index, position))
+      return false;
+    indices.push_back(vertex.value());
+    // kInvalidCornerIndex is UINT32_MAX, which Rust reads as no opposite.
+    opposites.push_back(table.Opposite(c).value());
   }
 
-  std::sort(attrs.begin(), attrs.end(),
-            [](const AttrEntry &a, const AttrEntry &b) {
-              return a.attribute_id < b.attribute_id;
-            });
+  positions.reserve(vertex_positions.size());
+  for (float v : vertex_positions) {
+    positions.push_back(v);
+  }
+  return true;
+}
 
-  // --------- WRITE ATTRIBUTES IN SORTED ORDER ----------
-  int num_points = mesh->num_points();
-
-  for (auto &entry : attrs) {
-    const draco::PointAttribute *attr = entry.attr;
-    int dim = attr->num_components();
-    draco::DataType type = attr->data_type();
-
-    for (draco::PointIndex j(0); j < num_points; ++j) {
-      switch (type) {
-      case draco::DT_INT8: {
-        int8_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_UINT8: {
-        uint8_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_INT16: {
-        int16_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_UINT16: {
-        uint16_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_INT32: {
-        int32_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_UINT32: {
-        uint32_t v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_FLOAT32: {
-        float v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      case draco::DT_FLOAT64: {
-        double v[4] = {};
-        attr->ConvertValue(attr->mapped_index(j), v);
-        for (int k = 0; k < dim; ++k)
-          if (!write_scalar(&v[k], type))
-            return 0;
-        break;
-      }
-      default:
-        return 0;
-      }
-    }
+bool decode_connectivity(rust::Slice<const uint8_t> data,
+                         rust::Vec<uint32_t> &indices,
+                         rust::Vec<uint32_t> &opposites,
+                         rust::Vec<float> &positions) {
+  draco::DecoderBuffer buffer;
+  buffer.Init(reinterpret_cast<const char *>(data.data()), data.size());
+
+  draco::DracoHeader header;
+  draco::DecoderBuffer header_buffer(buffer);
+  if (!draco::PointCloudDecoder::DecodeHeader(&header_buffer, &header).ok() ||
+      header.encoder_type != draco::TRIANGULAR_MESH) {
+    return false;
+  }
+  // The mesh decoder is kept instead of going through draco::Decoder, which
+  // discards it with the corner table edgebreaker builds while decoding.
+  std::unique_ptr<draco::MeshDecoder> decoder;
+  if (header.encoder_method == draco::MESH_EDGEBREAKER_ENCODING) {
+    decoder = std::make_unique<draco::MeshEdgebreakerDecoder>();
+  } else if (header.encoder_method == draco::MESH_SEQUENTIAL_ENCODING) {
+    decoder = std::make_unique<draco::MeshSequentialDecoder>();
+  } else {
+    return false;
+  }
+
+  // Draco can't skip decoding attributes, but leaves all but POSITION
+  // quantized.
+  draco::DecoderOptions options;
+  for (int type = draco::GeometryAttribute::POSITION + 1;
+       type < draco::GeometryAttribute::NAMED_ATTRIBUTES_COUNT; ++type) {
+    options.SetAttributeBool(static_cast<draco::GeometryAttribute::Type>(type),
+                             "skip_attribute_transform", true);
+  }
+  draco::Mesh mesh;
+  if (!decoder->Decode(options, &buffer, &mesh).ok()) {
+    return false;
   }
 
-  return static_cast<size_t>(out - out_ptr);
+  const draco::PointAttribute *attr =
+      mesh.GetNamedAttribute(draco::GeometryAttribute::POSITION);
+  if (!attr || attr->num_components() < 1 || attr->num_components() > 3) {
+    return false;
+  }
+  const draco::CornerTable *table = decoder->GetCornerTable();
+  if (!table) {
+    return write_position_values(mesh, attr, indices, positions);
+  }
+  return write_corner_table(mesh, attr, *table, indices, opposites, positions);
+}
+#else
+bool decode_connectivity(rust::Slice<const uint8_t>, rust::Vec<uint32_t> &,
+                         rust::Vec<uint32_t> &, rust::Vec<float> &) {
+  return false;
 }
+#endif
 
 size_t debug_mesh_buffer_len(const uint8_t *data, size_t data_len) {
   draco::DecoderBuffer buffer;
@@ -234,16 +535,16 @@ size_t debug_mesh_buffer_len(const uint8_t *data, size_t data_len) {
   }
   std::unique_ptr<draco::Mesh> mesh = std::move(status_or_geometry).value();
 
-  const int num_faces = mesh->num_faces();
-  const int num_indices = num_faces * 3;
+  const size_t num_faces = mesh->num_faces();
+  const size_t num_indices = num_faces * 3;
 
   const int attr_count = mesh->num_attributes();
-  const int num_points = mesh->num_points();
+  const size_t num_points = mesh->num_points();
 
   size_t size = 0;
 
   size_t index_bytes = 0;
-  if (num_indices <= static_cast<int>(std::numeric_limits<uint16_t>::max())) {
+  if (num_indices <= std::numeric_limits<uint16_t>::max()) {
     index_bytes = num_indices * sizeof(uint16_t);
   } else {
     index_bytes = num_indices * sizeof(uint32_t);
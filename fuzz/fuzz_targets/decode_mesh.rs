@@ -0,0 +1,27 @@
+#![no_main]
+
+use draco_decoder::{AttributeDataType, DecodeLimits, DracoDecodeConfig, decode_mesh};
+use libfuzzer_sys::fuzz_target;
+
+// The first 4 bytes pick the vertex and face counts the caller claims, the rest is the
+// Draco bitstream, so mismatching configs get exercised as well as hostile files.
+fuzz_target!(|data: &[u8]| {
+    let Some((counts, draco)) = data.split_first_chunk::<4>() else {
+        return;
+    };
+    let vertex_count = u16::from_le_bytes([counts[0], counts[1]]) as u32;
+    let index_count = u16::from_le_bytes([counts[2], counts[3]]) as u32 * 3;
+
+    let mut config = DracoDecodeConfig::new(vertex_count, index_count);
+    config.add_attribute(3, AttributeDataType::Float32);
+    config.add_attribute(3, AttributeDataType::Float32);
+    config.add_attribute(2, AttributeDataType::Float32);
+    config.set_limits(
+        DecodeLimits::new()
+            .with_max_points(1 << 20)
+            .with_max_faces(1 << 20)
+            .with_max_output_bytes(64 << 20),
+    );
+
+    let _ = pollster::block_on(decode_mesh(draco, &config));
+});
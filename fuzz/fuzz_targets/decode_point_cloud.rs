@@ -0,0 +1,11 @@
+#![no_main]
+
+use draco_decoder::{DecodeLimits, decode_point_cloud};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits::new()
+        .with_max_points(1 << 20)
+        .with_max_output_bytes(64 << 20);
+    let _ = decode_point_cloud(data, &limits);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use draco_decoder::inspect_header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(header) = inspect_header(data) {
+        let _ = (header.version(), header.geometry_type(), header.has_metadata());
+    }
+});
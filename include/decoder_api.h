@@ -1,12 +1,49 @@
 #pragma once
 #include "draco_decoder/src/ffi.rs.h"
+#include "draco/mesh/mesh.h"
+#include "draco/point_cloud/point_cloud.h"
 #include "rust/cxx.h"
 #include <cstdint>
+#include <memory>
 #include <vector>
 
+// A decoded point cloud or mesh, kept alive on the C++ side so Rust can
+// inspect its layout before providing the output buffer.
+class DracoGeometry {
+public:
+  DracoGeometry(std::unique_ptr<draco::PointCloud> point_cloud,
+                const draco::Mesh *mesh);
+
+  const draco::PointCloud &point_cloud() const { return *point_cloud_; }
+  // Null when the bitstream is a point cloud.
+  const draco::Mesh *mesh() const { return mesh_; }
+  // Attributes in output order, sorted by unique id.
+  const std::vector<const draco::PointAttribute *> &attributes() const {
+    return attributes_;
+  }
+
+private:
+  std::unique_ptr<draco::PointCloud> point_cloud_;
+  const draco::Mesh *mesh_;
+  std::vector<const draco::PointAttribute *> attributes_;
+};
+
 rust::Vec<uint8_t> decode_point_cloud(rust::Slice<const uint8_t> data);
 
-size_t decode_mesh_direct_write(const uint8_t *data, size_t data_len,
-                                uint8_t *out_ptr, size_t out_len);
+std::unique_ptr<DracoGeometry> decode_geometry(rust::Slice<const uint8_t> data,
+                                               const DecodeOptions &options,
+                                               StageTimer &timer);
+
+GeometryInfo geometry_info(const DracoGeometry &geometry);
+
+bool write_geometry(const DracoGeometry &geometry, uint8_t *out_ptr,
+                    size_t out_len, rust::Slice<const AttributeOutput> outputs,
+                    bool per_corner, bool wide_indices,
+                    rust::Vec<AttributeStats> &stats);
+
+bool decode_connectivity(rust::Slice<const uint8_t> data,
+                         rust::Vec<uint32_t> &indices,
+                         rust::Vec<uint32_t> &opposites,
+                         rust::Vec<float> &positions);
 
 size_t debug_mesh_buffer_len(const uint8_t *data, size_t data_len);
@@ -0,0 +1,91 @@
+// Loader used outside the browser main thread.
+//
+// The bundled decoder (index.es.js) spawns a browser `Worker` from a data: URL
+// as soon as it is imported. Node has no global `Worker`, Deno only accepts
+// module workers, and some embedders expose no workers at all, so a compatible
+// `Worker` global is installed here before the bundle is imported from a data:
+// URL (neither Node nor Deno can import blob: URLs).
+//
+// The worker source is evaluated with `process` shadowed so the Emscripten
+// runtime takes its shell path and instantiates the embedded wasm binary
+// instead of reaching for `fs`.
+(function (runtime, code) {
+  function workerSource(url) {
+    return decodeURIComponent(url.slice(url.indexOf(",") + 1));
+  }
+
+  async function installWorker() {
+    if (runtime === "node") {
+      const threads = await import("node:worker_threads");
+      const prelude = [
+        'const { parentPort } = require("node:worker_threads");',
+        "globalThis.self = globalThis;",
+        "self.postMessage = (data, transfer) => parentPort.postMessage(data, transfer);",
+        'parentPort.on("message", (data) => self.onmessage && self.onmessage({ data }));',
+      ].join("\n");
+
+      globalThis.Worker = class {
+        constructor(url, options) {
+          const source = `${prelude}\n(function (process) {\n${workerSource(url)}\n})();`;
+          this.pending = 0;
+          this.worker = new threads.Worker(source, {
+            eval: true,
+            name: options && options.name,
+          });
+          // Only keep the process alive while a decode is in flight.
+          this.worker.unref();
+          this.worker.on("message", (data) => {
+            if (--this.pending === 0) this.worker.unref();
+            if (this.onmessage) this.onmessage({ data });
+          });
+        }
+
+        postMessage(data, transfer) {
+          if (this.pending++ === 0) this.worker.ref();
+          this.worker.postMessage(data, transfer);
+        }
+
+        addEventListener(type, listener) {
+          this.worker.on(type, listener);
+        }
+
+        terminate() {
+          return this.worker.terminate();
+        }
+      };
+    } else if (runtime === "deno") {
+      const ModuleWorker = globalThis.Worker;
+      globalThis.Worker = class extends ModuleWorker {
+        constructor(url, options) {
+          super(url, Object.assign({}, options, { type: "module" }));
+        }
+      };
+    } else if (runtime === "inline") {
+      // No workers available: run the decoder in this isolate. Messages are
+      // still delivered asynchronously so callers see the same protocol.
+      globalThis.Worker = class {
+        constructor(url) {
+          const owner = this;
+          this.scope = {
+            postMessage(data) {
+              queueMicrotask(() => owner.onmessage && owner.onmessage({ data }));
+            },
+          };
+          new Function("self", "process", workerSource(url))(this.scope, undefined);
+        }
+
+        postMessage(data) {
+          queueMicrotask(() => this.scope.onmessage({ data }));
+        }
+
+        addEventListener() {}
+
+        terminate() {}
+      };
+    }
+  }
+
+  return installWorker().then(() =>
+    import("data:text/javascript;charset=utf-8," + encodeURIComponent(code))
+  );
+})
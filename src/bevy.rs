@@ -0,0 +1,258 @@
+//! Bevy integration: an [`AssetLoader`] for `.drc` files, [`to_bevy_mesh`], and with the
+//! `bevy-gltf` feature a glTF loader that understands `KHR_draco_mesh_compression`.
+
+use std::fmt;
+
+use ::bevy::app::{App, Plugin};
+use ::bevy::asset::io::Reader;
+use ::bevy::asset::{AssetApp, AssetLoader, LoadContext, RenderAssetUsages};
+use ::bevy::mesh::{Indices, Mesh, PrimitiveTopology, VertexAttributeValues};
+use ::bevy::reflect::TypePath;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::read_f32s;
+use crate::skinning::normalize_weights;
+use crate::{AttributeSemantic, DecodeError, DecodeLimits, DecodedMesh, IndexFormat};
+
+#[cfg(feature = "bevy-gltf")]
+mod gltf;
+#[cfg(feature = "bevy-gltf")]
+pub use gltf::DracoGltfLoader;
+
+/// Registers [`DracoMeshLoader`], and [`DracoGltfLoader`] in place of Bevy's glTF loader
+/// when the `bevy-gltf` feature is enabled. Add it after `DefaultPlugins`.
+#[derive(Default)]
+pub struct DracoPlugin;
+
+impl Plugin for DracoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Mesh>()
+            .register_asset_loader(DracoMeshLoader);
+    }
+
+    #[cfg(feature = "bevy-gltf")]
+    fn finish(&self, app: &mut App) {
+        let loader = DracoGltfLoader::from_app(app);
+        app.register_asset_loader(loader);
+    }
+}
+
+#[derive(Debug)]
+pub enum DracoLoaderError {
+    Io(std::io::Error),
+    Decode(DecodeError),
+    /// The decoded mesh can't be represented as a Bevy mesh, or the target can't decode it.
+    Unsupported(String),
+    #[cfg(feature = "bevy-gltf")]
+    InvalidGltf(String),
+    #[cfg(feature = "bevy-gltf")]
+    ReadBuffer(Box<::bevy::asset::ReadAssetBytesError>),
+    #[cfg(feature = "bevy-gltf")]
+    Gltf(Box<::bevy::gltf::GltfError>),
+}
+
+impl fmt::Display for DracoLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DracoLoaderError::Io(err) => write!(f, "failed to read draco asset: {err}"),
+            DracoLoaderError::Decode(err) => write!(f, "failed to decode draco asset: {err}"),
+            DracoLoaderError::Unsupported(reason) => write!(f, "unsupported draco asset: {reason}"),
+            #[cfg(feature = "bevy-gltf")]
+            DracoLoaderError::InvalidGltf(reason) => write!(f, "invalid glTF: {reason}"),
+            #[cfg(feature = "bevy-gltf")]
+            DracoLoaderError::ReadBuffer(err) => write!(f, "failed to read glTF buffer: {err}"),
+            #[cfg(feature = "bevy-gltf")]
+            DracoLoaderError::Gltf(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for DracoLoaderError {}
+
+impl From<std::io::Error> for DracoLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        DracoLoaderError::Io(err)
+    }
+}
+
+impl From<DecodeError> for DracoLoaderError {
+    fn from(err: DecodeError) -> Self {
+        DracoLoaderError::Decode(err)
+    }
+}
+
+#[cfg(feature = "bevy-gltf")]
+impl From<crate::gltf::GltfError> for DracoLoaderError {
+    fn from(err: crate::gltf::GltfError) -> Self {
+        match err {
+            crate::gltf::GltfError::Invalid(reason) => DracoLoaderError::InvalidGltf(reason),
+            crate::gltf::GltfError::Decode(err) => DracoLoaderError::Decode(err),
+            err => DracoLoaderError::InvalidGltf(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DracoLoaderSettings {
+    pub asset_usage: RenderAssetUsages,
+    pub limits: DecodeLimits,
+}
+
+/// Loads `.drc` meshes, taking the layout from the bitstream. Native only, on wasm the
+/// layout has to be known up front, see [`DracoGltfLoader`] for glTF files.
+#[derive(Default, TypePath)]
+pub struct DracoMeshLoader;
+
+impl AssetLoader for DracoMeshLoader {
+    type Asset = Mesh;
+    type Settings = DracoLoaderSettings;
+    type Error = DracoLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DracoLoaderSettings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Mesh, DracoLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let decoded = crate::decode_mesh_auto(&bytes, &settings.limits).await?;
+            to_bevy_mesh(&decoded, settings.asset_usage)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = settings;
+            Err(DracoLoaderError::Unsupported(
+                "loading .drc files without a layout is only supported natively".to_string(),
+            ))
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["drc"]
+    }
+}
+
+/// Builds a triangle list mesh. Positions, normals, `TexCoord(0..=1)`, `Color(0)`, tangents,
+/// `Joints(0)` and `Weights(0)` map to the matching `Mesh::ATTRIBUTE_*`, everything else is
+/// skipped. Integer colors, texcoords and weights are treated as normalized, as in glTF,
+/// other attributes only when [`crate::MeshAttribute::normalized`] is set. Weights are
+/// rescaled to sum to 1.
+pub fn to_bevy_mesh(
+    mesh: &DecodedMesh,
+    asset_usage: RenderAssetUsages,
+) -> Result<Mesh, DracoLoaderError> {
+    let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList, asset_usage);
+
+    let index_bytes = mesh.index_bytes();
+    bevy_mesh.insert_indices(match mesh.index_format() {
+        IndexFormat::U16 => Indices::U16(bytemuck::pod_collect_to_vec(index_bytes)),
+        IndexFormat::U32 => Indices::U32(bytemuck::pod_collect_to_vec(index_bytes)),
+    });
+
+    for (index, attr) in mesh.config().attribute_slice().iter().enumerate() {
+        let Some(semantic) = attr.semantic() else {
+            continue;
+        };
+        let Some(bytes) = mesh.packed_attribute_bytes(index) else {
+            continue;
+        };
+        let bytes = bytes.as_ref();
+        let dim = attr.dim();
+        let unsupported = || {
+            DracoLoaderError::Unsupported(format!(
+                "{semantic:?} attribute with {dim} {:?} components",
+                attr.data_type()
+            ))
+        };
+
+        match semantic {
+            AttributeSemantic::Position | AttributeSemantic::Normal => {
+                if dim != 3 {
+                    return Err(unsupported());
+                }
+                let values = read_f32s(attr.data_type(), bytes, attr.normalized());
+                let attribute = if semantic == AttributeSemantic::Position {
+                    Mesh::ATTRIBUTE_POSITION
+                } else {
+                    Mesh::ATTRIBUTE_NORMAL
+                };
+                bevy_mesh
+                    .insert_attribute(attribute, VertexAttributeValues::Float32x3(chunks(&values)));
+            }
+            AttributeSemantic::TexCoord(set @ (0 | 1)) => {
+                if dim != 2 {
+                    return Err(unsupported());
+                }
+                let values = read_f32s(attr.data_type(), bytes, true);
+                let attribute = if set == 0 {
+                    Mesh::ATTRIBUTE_UV_0
+                } else {
+                    Mesh::ATTRIBUTE_UV_1
+                };
+                bevy_mesh
+                    .insert_attribute(attribute, VertexAttributeValues::Float32x2(chunks(&values)));
+            }
+            AttributeSemantic::Color(0) => {
+                let values = read_f32s(attr.data_type(), bytes, true);
+                let colors: Vec<[f32; 4]> = match dim {
+                    3 => values
+                        .chunks_exact(3)
+                        .map(|c| [c[0], c[1], c[2], 1.0])
+                        .collect(),
+                    4 => chunks(&values),
+                    _ => return Err(unsupported()),
+                };
+                bevy_mesh.insert_attribute(
+                    Mesh::ATTRIBUTE_COLOR,
+                    VertexAttributeValues::Float32x4(colors),
+                );
+            }
+            AttributeSemantic::Tangent | AttributeSemantic::Weights(0) => {
+                if dim != 4 {
+                    return Err(unsupported());
+                }
+                let normalized = attr.normalized() || semantic != AttributeSemantic::Tangent;
+                let mut values = read_f32s(attr.data_type(), bytes, normalized);
+                let attribute = if semantic == AttributeSemantic::Tangent {
+                    Mesh::ATTRIBUTE_TANGENT
+                } else {
+                    normalize_weights(&mut values);
+                    Mesh::ATTRIBUTE_JOINT_WEIGHT
+                };
+                bevy_mesh
+                    .insert_attribute(attribute, VertexAttributeValues::Float32x4(chunks(&values)));
+            }
+            AttributeSemantic::Joints(0) => {
+                if dim != 4 {
+                    return Err(unsupported());
+                }
+                let joints: Vec<u16> = read_f32s(attr.data_type(), bytes, false)
+                    .into_iter()
+                    .map(|joint| joint as u16)
+                    .collect();
+                bevy_mesh.insert_attribute(
+                    Mesh::ATTRIBUTE_JOINT_INDEX,
+                    VertexAttributeValues::Uint16x4(chunks(&joints)),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(bevy_mesh)
+}
+
+fn chunks<T: Copy + Default, const N: usize>(values: &[T]) -> Vec<[T; N]> {
+    values
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut out = [T::default(); N];
+            out.copy_from_slice(chunk);
+            out
+        })
+        .collect()
+}
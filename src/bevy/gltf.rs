@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex};
+
+use ::bevy::app::App;
+use ::bevy::asset::io::Reader;
+use ::bevy::asset::{AssetLoader, LoadContext};
+use ::bevy::gltf::extensions::GltfExtensionHandlers;
+use ::bevy::gltf::{DefaultGltfImageSampler, Gltf, GltfLoader, GltfLoaderSettings, GltfPlugin};
+use ::bevy::image::{CompressedImageFormatSupport, CompressedImageFormats, ImageSamplerDescriptor};
+use ::bevy::reflect::TypePath;
+use serde_json::{Value, json};
+
+use super::DracoLoaderError;
+use crate::gltf::{
+    CHUNK_BIN, CHUNK_JSON, DecodeKey, GLB_MAGIC, KHR_DRACO, decode_data_uri, decode_key,
+    parse_gltf, primitive_config,
+};
+use crate::{BufferLayout, IndexFormat, try_decode_mesh};
+
+/// Bevy's glTF loader with `KHR_draco_mesh_compression` support. Compressed primitives are
+/// decoded with this crate and written back as plain accessors before the file is handed to
+/// [`GltfLoader`], so everything else behaves exactly like Bevy's loader.
+#[derive(TypePath)]
+pub struct DracoGltfLoader {
+    gltf: GltfLoader,
+}
+
+impl DracoGltfLoader {
+    pub fn new(gltf: GltfLoader) -> Self {
+        Self { gltf }
+    }
+
+    /// Configures the inner loader like [`GltfPlugin`] does. Call once `GltfPlugin` is finished.
+    pub fn from_app(app: &App) -> Self {
+        let world = app.world();
+        let supported_compressed_formats = world
+            .get_resource::<CompressedImageFormatSupport>()
+            .map_or(CompressedImageFormats::NONE, |support| support.0);
+        let default_sampler = world.get_resource::<DefaultGltfImageSampler>().map_or_else(
+            || Arc::new(Mutex::new(ImageSamplerDescriptor::default())),
+            DefaultGltfImageSampler::get_internal,
+        );
+        let extensions = world
+            .get_resource::<GltfExtensionHandlers>()
+            .map(|handlers| handlers.0.clone())
+            .unwrap_or_default();
+        let plugin = app.get_added_plugins::<GltfPlugin>();
+        let (custom_vertex_attributes, default_convert_coordinates) = plugin
+            .first()
+            .map(|plugin| {
+                (
+                    plugin.custom_vertex_attributes.clone(),
+                    plugin.convert_coordinates,
+                )
+            })
+            .unwrap_or_default();
+
+        Self::new(GltfLoader {
+            supported_compressed_formats,
+            custom_vertex_attributes,
+            default_sampler,
+            default_convert_coordinates,
+            extensions,
+        })
+    }
+}
+
+impl AssetLoader for DracoGltfLoader {
+    type Asset = Gltf;
+    type Settings = GltfLoaderSettings;
+    type Error = DracoLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &GltfLoaderSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Gltf, DracoLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let bytes = match decompress_gltf(&bytes, load_context).await? {
+            Some(glb) => glb,
+            None => bytes,
+        };
+        GltfLoader::load_gltf(&self.gltf, &bytes, load_context, settings)
+            .await
+            .map_err(|err| DracoLoaderError::Gltf(Box::new(err)))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> DracoLoaderError {
+    DracoLoaderError::InvalidGltf(reason.into())
+}
+
+fn write_glb(json: &Value, bin: &[u8]) -> Vec<u8> {
+    let mut json = serde_json::to_vec(json).expect("glTF JSON is serializable");
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let bin_len = bin.len().next_multiple_of(4);
+    let total = 12 + 8 + json.len() + 8 + bin_len;
+
+    let mut glb = Vec::with_capacity(total);
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(total as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin_len as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+    glb.extend_from_slice(bin);
+    glb.resize(total, 0);
+    glb
+}
+
+fn has_draco_primitives(json: &Value) -> bool {
+    json["meshes"].as_array().is_some_and(|meshes| {
+        meshes.iter().any(|mesh| {
+            mesh["primitives"].as_array().is_some_and(|primitives| {
+                primitives
+                    .iter()
+                    .any(|primitive| primitive["extensions"].get(KHR_DRACO).is_some())
+            })
+        })
+    })
+}
+
+async fn load_buffers(
+    json: &Value,
+    blob: Option<&[u8]>,
+    load_context: &mut LoadContext<'_>,
+) -> Result<Vec<Vec<u8>>, DracoLoaderError> {
+    let mut buffers = Vec::new();
+    for buffer in json["buffers"].as_array().into_iter().flatten() {
+        let data = match buffer["uri"].as_str() {
+            None => blob
+                .ok_or_else(|| invalid("buffer without uri or GLB blob"))?
+                .to_vec(),
+            Some(uri) if uri.starts_with("data:") => decode_data_uri(uri)?,
+            Some(uri) => {
+                let path = load_context
+                    .path()
+                    .resolve_embed(uri)
+                    .map_err(|err| invalid(err.to_string()))?;
+                load_context
+                    .read_asset_bytes(path)
+                    .await
+                    .map_err(|err| DracoLoaderError::ReadBuffer(Box::new(err)))?
+            }
+        };
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+fn buffer_view_bytes<'a>(
+    json: &Value,
+    buffers: &'a [Vec<u8>],
+    view: usize,
+) -> Result<&'a [u8], DracoLoaderError> {
+    let view = &json["bufferViews"][view];
+    let buffer = view["buffer"]
+        .as_u64()
+        .and_then(|buffer| buffers.get(buffer as usize))
+        .ok_or_else(|| invalid("bufferView without buffer"))?;
+    let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+    let length = view["byteLength"].as_u64().unwrap_or(0) as usize;
+    offset
+        .checked_add(length)
+        .and_then(|end| buffer.get(offset..end))
+        .ok_or_else(|| invalid("bufferView out of range"))
+}
+
+/// Appends a bufferView over `range` of the decoded data at `base` in buffer 0.
+fn push_buffer_view(json: &mut Value, base: usize, range: std::ops::Range<usize>) -> usize {
+    let views = json["bufferViews"]
+        .as_array_mut()
+        .expect("bufferViews is an array");
+    views.push(json!({
+        "buffer": 0,
+        "byteOffset": base + range.start,
+        "byteLength": range.len(),
+    }));
+    views.len() - 1
+}
+
+fn point_accessor(json: &mut Value, accessor: usize, view: usize, component_type: Option<u64>) {
+    let accessor = json["accessors"][accessor]
+        .as_object_mut()
+        .expect("accessor is an object");
+    accessor.insert("bufferView".into(), view.into());
+    accessor.remove("byteOffset");
+    if let Some(component_type) = component_type {
+        accessor.insert("componentType".into(), component_type.into());
+    }
+}
+
+/// Decodes every `KHR_draco_mesh_compression` primitive and returns an equivalent GLB
+/// without the extension, or `None` if the file has no compressed primitives.
+async fn decompress_gltf(
+    bytes: &[u8],
+    load_context: &mut LoadContext<'_>,
+) -> Result<Option<Vec<u8>>, DracoLoaderError> {
+    let (mut json, blob) = parse_gltf(bytes)?;
+    if !has_draco_primitives(&json) {
+        return Ok(None);
+    }
+    let mut buffers = load_buffers(&json, blob.as_deref(), load_context).await?;
+
+    // Decoded data goes into the GLB buffer, which has to be buffer 0.
+    let has_glb_buffer = blob.is_some() && json["buffers"][0].get("uri").is_none();
+    let mut bin = if has_glb_buffer {
+        buffers[0].clone()
+    } else {
+        for view in json["bufferViews"].as_array_mut().into_iter().flatten() {
+            let buffer = view["buffer"].as_u64().unwrap_or(0);
+            view["buffer"] = (buffer + 1).into();
+        }
+        let root = json
+            .as_object_mut()
+            .ok_or_else(|| invalid("glTF root is not an object"))?;
+        if let Some(list) = root
+            .entry("buffers")
+            .or_insert_with(|| Value::Array(Vec::new()))
+            .as_array_mut()
+        {
+            list.insert(0, json!({ "byteLength": 0 }));
+        }
+        buffers.insert(0, Vec::new());
+        Vec::new()
+    };
+    if json.get("bufferViews").is_none() {
+        json["bufferViews"] = Value::Array(Vec::new());
+    }
+
+    // Primitives sharing a compressed bufferView and its attributes are decoded once.
+    let mut decoded: HashMap<DecodeKey, (usize, BufferLayout)> = HashMap::new();
+    let mesh_count = json["meshes"].as_array().map_or(0, Vec::len);
+    for mesh in 0..mesh_count {
+        let primitive_count = json["meshes"][mesh]["primitives"]
+            .as_array()
+            .map_or(0, Vec::len);
+        for primitive in 0..primitive_count {
+            let primitive_json = &json["meshes"][mesh]["primitives"][primitive];
+            let Some(view) = primitive_json["extensions"][KHR_DRACO]["bufferView"].as_u64() else {
+                continue;
+            };
+            let (config, attributes) = primitive_config(&json, primitive_json)?;
+            let accessors: Vec<usize> = attributes.iter().map(|attr| attr.accessor).collect();
+            let indices = primitive_json["indices"].as_u64().map(|i| i as usize);
+
+            let (base, layout) = match decoded.entry(decode_key(view, &config)) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => {
+                    let compressed = buffer_view_bytes(&json, &buffers, view as usize)?;
+                    let data = try_decode_mesh(compressed, &config).await?;
+                    bin.resize(bin.len().next_multiple_of(4), 0);
+                    let base = bin.len();
+                    bin.extend_from_slice(&data);
+                    entry.insert((base, config.layout())).clone()
+                }
+            };
+
+            if let Some(indices) = indices {
+                let component_type = match layout.indices.format {
+                    IndexFormat::U16 => 5123,
+                    IndexFormat::U32 => 5125,
+                };
+                let index_view = push_buffer_view(&mut json, base, layout.indices.byte_range);
+                point_accessor(&mut json, indices, index_view, Some(component_type));
+            }
+            // Compressed morph targets get plain accessors too.
+            for (accessor, attr) in accessors.into_iter().zip(layout.attributes) {
+                let attr_view = push_buffer_view(&mut json, base, attr.byte_range);
+                point_accessor(&mut json, accessor, attr_view, None);
+            }
+
+            let primitive_json = &mut json["meshes"][mesh]["primitives"][primitive];
+            if let Some(extensions) = primitive_json["extensions"].as_object_mut() {
+                extensions.remove(KHR_DRACO);
+                if extensions.is_empty() {
+                    primitive_json
+                        .as_object_mut()
+                        .map(|primitive| primitive.remove("extensions"));
+                }
+            }
+        }
+    }
+
+    for key in ["extensionsUsed", "extensionsRequired"] {
+        if let Some(list) = json[key].as_array_mut() {
+            list.retain(|ext| ext.as_str() != Some(KHR_DRACO));
+        }
+    }
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    json["buffers"][0]["byteLength"] = bin.len().into();
+
+    Ok(Some(write_glb(&json, &bin)))
+}
@@ -0,0 +1,181 @@
+//! A reusable native decoder that keeps its options and output buffer between calls and
+//! records what it decoded.
+
+use crate::ffi::{decode_for_config, write_mesh_into};
+use crate::timing::{Stopwatch, trace_span};
+use crate::{
+    AttributeSemantic, BufferLayout, DecodeError, DecodeLimits, DecodeTimings, DecodedMesh,
+    DracoDecodeConfig, IndexFormat, MeshStats, VertexLayout,
+};
+
+/// Totals over every call of a [`DracoDecoder`] since it was created or reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Successful decodes.
+    pub decodes: u64,
+    /// Decodes that returned an error.
+    pub failures: u64,
+    /// Bitstream bytes of successful decodes.
+    pub bytes_in: u64,
+    /// Output bytes written by successful decodes.
+    pub bytes_out: u64,
+    /// Time spent in each phase, including by failed decodes.
+    pub timings: DecodeTimings,
+}
+
+/// Decodes meshes with the same options, e.g. all tiles of a dataset. Only the options and
+/// the output buffer of [`Self::decode_to_scratch`] carry over between calls; Draco's own
+/// decoder and buffers are created for every call.
+///
+/// ```no_run
+/// # use draco_decoder::{AttributeDataType, DracoDecodeConfig, DracoDecoder, IndexFormat};
+/// # let tiles: Vec<Vec<u8>> = Vec::new();
+/// let mut config = DracoDecodeConfig::new(3254, 4368);
+/// config.add_attribute(3, AttributeDataType::Float32);
+/// let mut decoder = DracoDecoder::new(config).with_index_format(IndexFormat::U32);
+/// for tile in &tiles {
+///     let buf = decoder.decode_to_scratch(tile)?;
+///     // upload `buf` before the next decode overwrites it
+/// }
+/// println!("{:?}", decoder.stats());
+/// # Ok::<(), draco_decoder::DecodeError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct DracoDecoder {
+    config: DracoDecodeConfig,
+    skip_transforms: u32,
+    scratch: Vec<u8>,
+    stats: DecoderStats,
+}
+
+impl DracoDecoder {
+    pub fn new(config: DracoDecodeConfig) -> Self {
+        Self {
+            config,
+            skip_transforms: 0,
+            scratch: Vec::new(),
+            stats: DecoderStats::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.config.set_limits(limits);
+        self
+    }
+
+    pub fn with_index_format(mut self, index_format: IndexFormat) -> Self {
+        self.config.set_index_format(index_format);
+        self
+    }
+
+    pub fn with_vertex_layout(mut self, vertex_layout: VertexLayout) -> Self {
+        self.config.set_vertex_layout(vertex_layout);
+        self
+    }
+
+    /// Keeps attributes of the same Draco type as `semantic` as the quantized integers Draco
+    /// stores, e.g. for dequantization in a shader. Their values are `Int32`, and octahedral
+    /// normals have 2 components, so the config has to describe them that way.
+    ///
+    /// Draco only has its tangent, joints and weights types when built with transcoder
+    /// support, which this crate doesn't do, so those semantics fail with
+    /// [`DecodeError::UnsupportedSemantic`].
+    pub fn skip_attribute_transform(
+        mut self,
+        semantic: AttributeSemantic,
+    ) -> Result<Self, DecodeError> {
+        let draco_type = semantic.to_draco();
+        if draco_type >= AttributeSemantic::Tangent.to_draco() {
+            return Err(DecodeError::UnsupportedSemantic(semantic));
+        }
+        self.skip_transforms |= 1 << draco_type;
+        Ok(self)
+    }
+
+    pub fn config(&self) -> &DracoDecodeConfig {
+        &self.config
+    }
+
+    /// Replaces the config, with its limits, index format and vertex layout, for the next
+    /// decodes. Skipped transforms, the scratch buffer and the stats are kept.
+    pub fn set_config(&mut self, config: DracoDecodeConfig) {
+        self.config = config;
+    }
+
+    pub fn layout(&self) -> BufferLayout {
+        self.config.layout()
+    }
+
+    pub fn stats(&self) -> &DecoderStats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = DecoderStats::default();
+    }
+
+    /// Decodes into a new buffer, see [`crate::decode_mesh_with_stats`].
+    pub fn decode(&mut self, data: &[u8]) -> Result<DecodedMesh, DecodeError> {
+        let mut out = vec![0; self.output_size()?];
+        let stats = self.decode_into(data, &mut out)?;
+        Ok(DecodedMesh::new(out, self.config.clone()).with_stats(stats))
+    }
+
+    /// Decodes into the decoder's own buffer, which is reused by the next call instead of
+    /// allocating again.
+    pub fn decode_to_scratch(&mut self, data: &[u8]) -> Result<&[u8], DecodeError> {
+        let size = self.output_size()?;
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize(size, 0);
+        let result = self.decode_into(data, &mut scratch);
+        self.scratch = scratch;
+        result.map(|_| self.scratch.as_slice())
+    }
+
+    /// Decodes into `out`, see [`crate::decode_mesh_into`].
+    pub fn decode_into(&mut self, data: &[u8], out: &mut [u8]) -> Result<MeshStats, DecodeError> {
+        let result = self.decode_phases(data, out);
+        match result {
+            Ok(_) => {
+                self.stats.decodes += 1;
+                self.stats.bytes_in += data.len() as u64;
+                self.stats.bytes_out += self.config.estimate_buffer_size() as u64;
+            }
+            Err(_) => self.stats.failures += 1,
+        }
+        result
+    }
+
+    /// The size to allocate for the next decode, counting a failure if it is too large.
+    fn output_size(&mut self) -> Result<usize, DecodeError> {
+        let size = self.config.checked_output_size();
+        if size.is_err() {
+            self.stats.failures += 1;
+        }
+        size
+    }
+
+    fn decode_phases(&mut self, data: &[u8], out: &mut [u8]) -> Result<MeshStats, DecodeError> {
+        let needed = self.config.checked_output_size()?;
+        if out.len() < needed {
+            return Err(DecodeError::OutputTooSmall {
+                needed,
+                found: out.len(),
+            });
+        }
+
+        trace_span!("draco_decoder", input_bytes = data.len());
+        let accept_point_cloud = self.config.index_count() == 0;
+        let geometry = decode_for_config(
+            data,
+            &self.config,
+            accept_point_cloud,
+            self.skip_transforms,
+            &mut self.stats.timings,
+        )?;
+        let mut stopwatch = Stopwatch::start();
+        let stats = write_mesh_into(&geometry, &self.config, out);
+        self.stats.timings.write += stopwatch.lap();
+        stats
+    }
+}
@@ -0,0 +1,85 @@
+//! Vector types decoded attributes can be read as, see [`crate::DecodedMesh::positions`].
+
+/// A vector of `COMPONENTS` `f32`s. Implemented for `[f32; 2..=4]`, `[f32; 9]` and
+/// `[f32; 16]` (3×3 and 4×4 matrices, spherical harmonics), and for the glam and mint vector
+/// types with the `glam` and `mint` features.
+pub trait AttributeElement: Copy {
+    const COMPONENTS: usize;
+
+    /// Builds an element from exactly `COMPONENTS` values.
+    fn from_components(components: &[f32]) -> Self;
+
+    /// Reinterprets tightly packed `f32` components without copying, `None` if `bytes` is
+    /// not aligned for `Self` or not a whole number of elements.
+    fn cast_slice(bytes: &[u8]) -> Option<&[Self]>;
+}
+
+macro_rules! impl_array {
+    ($($n:literal),*) => {$(
+        impl AttributeElement for [f32; $n] {
+            const COMPONENTS: usize = $n;
+
+            fn from_components(components: &[f32]) -> Self {
+                let mut out = [0.0; $n];
+                out.copy_from_slice(components);
+                out
+            }
+
+            fn cast_slice(bytes: &[u8]) -> Option<&[Self]> {
+                bytemuck::try_cast_slice(bytes).ok()
+            }
+        }
+    )*};
+}
+
+impl_array!(2, 3, 4, 9, 16);
+
+#[cfg(feature = "glam")]
+macro_rules! impl_glam {
+    ($($ty:ty => $n:literal),*) => {$(
+        impl AttributeElement for $ty {
+            const COMPONENTS: usize = $n;
+
+            fn from_components(components: &[f32]) -> Self {
+                <$ty>::from_slice(components)
+            }
+
+            fn cast_slice(bytes: &[u8]) -> Option<&[Self]> {
+                bytemuck::try_cast_slice(bytes).ok()
+            }
+        }
+    )*};
+}
+
+// `Vec4` is 16 byte aligned unless glam uses scalar math, so it is usually copied.
+#[cfg(feature = "glam")]
+impl_glam!(glam::Vec2 => 2, glam::Vec3 => 3, glam::Vec4 => 4);
+
+#[cfg(feature = "mint")]
+macro_rules! impl_mint {
+    ($($ty:ty => $n:literal),*) => {$(
+        impl AttributeElement for $ty {
+            const COMPONENTS: usize = $n;
+
+            fn from_components(components: &[f32]) -> Self {
+                <[f32; $n]>::from_components(components).into()
+            }
+
+            fn cast_slice(bytes: &[u8]) -> Option<&[Self]> {
+                let arrays = <[f32; $n]>::cast_slice(bytes)?;
+                // SAFETY: mint vectors are `#[repr(C)]` structs of `$n` `f32` fields, so they
+                // have the size and alignment of `[f32; $n]`.
+                Some(unsafe { std::slice::from_raw_parts(arrays.as_ptr().cast(), arrays.len()) })
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "mint")]
+impl_mint!(
+    mint::Vector2<f32> => 2,
+    mint::Vector3<f32> => 3,
+    mint::Vector4<f32> => 4,
+    mint::Point2<f32> => 2,
+    mint::Point3<f32> => 3
+);
@@ -0,0 +1,57 @@
+use std::fmt;
+
+use crate::AttributeSemantic;
+use crate::validation::ConfigMismatch;
+
+/// Most components per attribute value Draco can convert, its component count is an `i8`.
+pub(crate) const MAX_COMPONENTS: u32 = i8::MAX as u32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Draco rejected the bitstream.
+    InvalidBitstream,
+    /// The bitstream holds a point cloud where a mesh was expected.
+    UnexpectedGeometryType,
+    /// One of the configured [`DecodeLimits`](crate::DecodeLimits) was exceeded.
+    LimitExceeded,
+    /// The config doesn't describe the bitstream, one entry per discrepancy.
+    ConfigMismatch(Vec<ConfigMismatch>),
+    /// Attribute `index` has more components than the decoder can write.
+    UnsupportedAttribute { index: usize, dim: u32 },
+    /// The output buffer is shorter than the config's layout.
+    OutputTooSmall { needed: usize, found: usize },
+    /// Draco, as this crate builds it, has no attribute type for the semantic.
+    UnsupportedSemantic(AttributeSemantic),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidBitstream => write!(f, "invalid draco bitstream"),
+            DecodeError::UnexpectedGeometryType => write!(f, "draco bitstream is not a mesh"),
+            DecodeError::LimitExceeded => write!(f, "decode limit exceeded"),
+            DecodeError::ConfigMismatch(mismatches) => {
+                write!(f, "decode config does not match the bitstream")?;
+                for (i, mismatch) in mismatches.iter().enumerate() {
+                    let separator = if i == 0 { ": " } else { "; " };
+                    write!(f, "{separator}{mismatch}")?;
+                }
+                Ok(())
+            }
+            DecodeError::UnsupportedAttribute { index, dim } => {
+                write!(
+                    f,
+                    "attribute {index} has {dim} components, at most {MAX_COMPONENTS} are supported"
+                )
+            }
+            DecodeError::OutputTooSmall { needed, found } => {
+                write!(f, "output buffer has {found} bytes, {needed} are needed")
+            }
+            DecodeError::UnsupportedSemantic(semantic) => {
+                write!(f, "draco has no attribute type for {semantic:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -1,51 +1,491 @@
-use crate::DracoDecodeConfig;
-#[cfg(feature = "perf")]
-use std::time::Instant;
+use crate::error::{DecodeError, MAX_COMPONENTS};
+use crate::header::{EncodedCounts, encoded_counts};
+use crate::mesh::{AttributeSemantic, DecodedMesh};
+use crate::stats::{AttributeStats, MeshStats};
+use crate::timing::{DecodeTimings, Stopwatch, trace_span};
+use crate::topology::ConnectivityMesh;
+use crate::validation::{FoundAttribute, attribute_sources, validate_config};
+use crate::{
+    AttributeDataType, DecodeLimits, DracoDecodeConfig, GeometryType, IndexFormat, OutputMode,
+    inspect_header,
+};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[cxx::bridge]
 mod cpp {
+    /// Bit `i` of `skip_transforms` keeps attributes of `draco::GeometryAttribute::Type` `i`
+    /// quantized. Limits are checked on the Rust side, against the counts the bitstream
+    /// declares before it is decoded and against the decoded geometry after.
+    struct DecodeOptions {
+        skip_transforms: u32,
+    }
+
+    /// Layout of a decoded attribute; types are the raw `draco::GeometryAttribute::Type`
+    /// and `draco::DataType` values.
+    struct AttributeInfo {
+        unique_id: u32,
+        attribute_type: i32,
+        data_type: i32,
+        num_components: u32,
+        normalized: bool,
+    }
+
+    /// Where to write an attribute: `source` is its index in [`GeometryInfo::attributes`],
+    /// `data_type` is a `draco::DataType`, `offset` is from the start of the output and
+    /// `stride` is the distance between values.
+    struct AttributeOutput {
+        source: usize,
+        data_type: i32,
+        offset: usize,
+        stride: usize,
+    }
+
+    /// Component-wise min, max and sum of an attribute as written, empty without points.
+    struct AttributeStats {
+        min: Vec<f64>,
+        max: Vec<f64>,
+        sum: Vec<f64>,
+    }
+
+    /// A stage of `draco::PointCloudDecoder::Decode` reported to [`StageTimer`]. Point clouds
+    /// have no connectivity, only their point count.
+    enum DecodeStage {
+        Connectivity,
+        Attributes,
+    }
+
+    struct GeometryInfo {
+        is_mesh: bool,
+        num_points: u32,
+        num_faces: u32,
+        /// Sorted by unique id, the order attributes are written in.
+        attributes: Vec<AttributeInfo>,
+    }
+
+    extern "Rust" {
+        type StageTimer;
+
+        fn begin(self: &mut StageTimer, stage: DecodeStage);
+        fn end(self: &mut StageTimer, stage: DecodeStage);
+    }
 
     unsafe extern "C++" {
         include!("draco_decoder/include/decoder_api.h");
 
         pub fn decode_point_cloud(data: &[u8]) -> Vec<u8>;
 
-        pub unsafe fn decode_mesh_direct_write(
-            data: *const u8,
-            data_len: usize,
+        type DracoGeometry;
+
+        /// Reports the connectivity and attribute stages to `timer` as Draco runs them.
+        pub fn decode_geometry(
+            data: &[u8],
+            options: &DecodeOptions,
+            timer: &mut StageTimer,
+        ) -> UniquePtr<DracoGeometry>;
+
+        pub fn geometry_info(geometry: &DracoGeometry) -> GeometryInfo;
+
+        /// Writes the indices followed by the attribute of every output, converted and placed
+        /// as described by it, and pushes the stats of every output.
+        /// `per_corner` writes a value per face corner and sequential indices, `wide_indices`
+        /// writes `u32` indices even if they fit in `u16`.
+        pub unsafe fn write_geometry(
+            geometry: &DracoGeometry,
             out_ptr: *mut u8,
             out_len: usize,
-        ) -> usize;
+            outputs: &[AttributeOutput],
+            per_corner: bool,
+            wide_indices: bool,
+            stats: &mut Vec<AttributeStats>,
+        ) -> bool;
+
+        /// Decodes a mesh leaving all attributes but POSITION quantized, and writes the
+        /// corner vertices and opposite corners of Draco's corner table with the positions
+        /// of its vertices as `[f32; 3]`. Meshes without one, i.e. sequential ones, get
+        /// their faces over the unique POSITION values and no opposites.
+        pub fn decode_connectivity(
+            data: &[u8],
+            indices: &mut Vec<u32>,
+            opposites: &mut Vec<u32>,
+            positions: &mut Vec<f32>,
+        ) -> bool;
 
         pub unsafe fn debug_mesh_buffer_len(data: *const u8, data_len: usize) -> usize;
     }
 }
 
-#[allow(dead_code)]
-pub fn decode_point_cloud_native(data: &[u8]) -> Vec<u8> {
-    cpp::decode_point_cloud(data)
+/// Times the stages Draco reports from inside [`cpp::decode_geometry`], each in its own
+/// `tracing` span.
+#[derive(Default)]
+pub(crate) struct StageTimer {
+    connectivity: Duration,
+    attributes: Duration,
+    stopwatch: Option<Stopwatch>,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::span::EnteredSpan>,
+}
+
+impl StageTimer {
+    fn begin(&mut self, stage: cpp::DecodeStage) {
+        #[cfg(feature = "tracing")]
+        {
+            let span = match stage {
+                cpp::DecodeStage::Connectivity => tracing::info_span!("connectivity"),
+                _ => tracing::info_span!("attributes"),
+            };
+            self.span = Some(span.entered());
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = stage;
+        self.stopwatch = Some(Stopwatch::start());
+    }
+
+    fn end(&mut self, stage: cpp::DecodeStage) {
+        let elapsed = self
+            .stopwatch
+            .take()
+            .map_or(Duration::ZERO, |mut stopwatch| stopwatch.lap());
+        match stage {
+            cpp::DecodeStage::Connectivity => self.connectivity += elapsed,
+            _ => self.attributes += elapsed,
+        }
+        #[cfg(feature = "tracing")]
+        {
+            self.span = None;
+        }
+    }
+}
+
+pub fn decode_point_cloud_native(data: &[u8], limits: &DecodeLimits) -> Vec<u8> {
+    // POSITION is written as `[f32; 3]` per point.
+    const POINT_SIZE: usize = 12;
+    let Ok(counts) = check_encoded_counts(data, limits) else {
+        return Vec::new();
+    };
+    if counts.is_some_and(|counts| !limits.allows_output_bytes(counts.points as usize * POINT_SIZE))
+    {
+        return Vec::new();
+    }
+
+    let out = cpp::decode_point_cloud(data);
+    let points = (out.len() / POINT_SIZE) as u32;
+    if check_counts(limits, points, 0).is_err() || !limits.allows_output_bytes(out.len()) {
+        return Vec::new();
+    }
+    out
+}
+
+/// Rejects a bitstream that declares more points or faces than `limits` allow before
+/// Draco allocates anything for them. Returns the declared counts if any limit is set.
+fn check_encoded_counts(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<Option<EncodedCounts>, DecodeError> {
+    if *limits == DecodeLimits::default() {
+        return Ok(None);
+    }
+    let counts = encoded_counts(data).ok_or(DecodeError::InvalidBitstream)?;
+    check_counts(limits, counts.points, counts.faces)?;
+    Ok(Some(counts))
+}
+
+fn check_counts(limits: &DecodeLimits, points: u32, faces: u32) -> Result<(), DecodeError> {
+    let too_many_points = limits.max_points().is_some_and(|max| points > max);
+    let too_many_faces = limits.max_faces().is_some_and(|max| faces > max);
+    if too_many_points || too_many_faces {
+        return Err(DecodeError::LimitExceeded);
+    }
+    Ok(())
+}
+
+/// Fails on the first output whose attribute the C++ side can't write, see
+/// [`attribute_sources`].
+fn check_dims(
+    info: &cpp::GeometryInfo,
+    sources: impl IntoIterator<Item = Option<usize>>,
+) -> Result<(), DecodeError> {
+    for (index, source) in sources.into_iter().enumerate() {
+        let dim = source.map_or(0, |source| info.attributes[source].num_components);
+        if dim > MAX_COMPONENTS {
+            return Err(DecodeError::UnsupportedAttribute { index, dim });
+        }
+    }
+    Ok(())
+}
+
+fn found_attributes(info: &cpp::GeometryInfo) -> Vec<FoundAttribute> {
+    info.attributes
+        .iter()
+        .map(|attr| FoundAttribute {
+            unique_id: attr.unique_id,
+            dim: attr.num_components,
+            data_type: AttributeDataType::from_draco(attr.data_type),
+        })
+        .collect()
+}
+
+fn decode_mesh_geometry(
+    data: &[u8],
+    limits: &DecodeLimits,
+    skip_transforms: u32,
+    timer: &mut StageTimer,
+) -> Result<(cxx::UniquePtr<cpp::DracoGeometry>, cpp::GeometryInfo), DecodeError> {
+    let (geometry, info) = decode_any_geometry(data, limits, skip_transforms, timer)?;
+    if !info.is_mesh {
+        return Err(DecodeError::UnexpectedGeometryType);
+    }
+    Ok((geometry, info))
+}
+
+fn decode_any_geometry(
+    data: &[u8],
+    limits: &DecodeLimits,
+    skip_transforms: u32,
+    timer: &mut StageTimer,
+) -> Result<(cxx::UniquePtr<cpp::DracoGeometry>, cpp::GeometryInfo), DecodeError> {
+    check_encoded_counts(data, limits)?;
+    let options = cpp::DecodeOptions { skip_transforms };
+    let geometry = cpp::decode_geometry(data, &options, timer);
+    let Some(geometry_ref) = geometry.as_ref() else {
+        return Err(DecodeError::InvalidBitstream);
+    };
+    let info = cpp::geometry_info(geometry_ref);
+    // Edgebreaker meshes declare fewer points than they decode to.
+    check_counts(limits, info.num_points, info.num_faces)?;
+    Ok((geometry, info))
 }
 
-pub async fn decode_mesh_native(data: &[u8], config: &DracoDecodeConfig) -> Option<Vec<u8>> {
-    #[cfg(feature = "perf")]
-    let start = Instant::now();
-    let mut out_buf = vec![0u8; config.estimate_buffer_size()];
+fn write_mesh(
+    geometry: &cpp::DracoGeometry,
+    config: &DracoDecodeConfig,
+) -> Result<(Vec<u8>, MeshStats), DecodeError> {
+    let mut out_buf = vec![0u8; config.layout().byte_length];
+    let stats = write_mesh_into(geometry, config, &mut out_buf)?;
+    Ok((out_buf, stats))
+}
+
+/// Writes the mesh into the first `config.layout().byte_length` bytes of `out`, which the
+/// caller has checked are there.
+pub fn write_mesh_into(
+    geometry: &cpp::DracoGeometry,
+    config: &DracoDecodeConfig,
+    out: &mut [u8],
+) -> Result<MeshStats, DecodeError> {
+    let layout = config.layout();
+    trace_span!(
+        "write_output",
+        output_bytes = layout.byte_length,
+        vertices = config.vertex_count(),
+        attributes = layout.attributes.len()
+    );
+    let sources = attribute_sources(config, &found_attributes(&cpp::geometry_info(geometry)));
+    let outputs = layout
+        .attributes
+        .iter()
+        .zip(sources)
+        .map(|(attr, source)| {
+            Some(cpp::AttributeOutput {
+                source: source?,
+                data_type: attr.data_type.to_draco(),
+                offset: attr.byte_range.start,
+                stride: attr.stride,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(DecodeError::InvalidBitstream)?;
+    let out = &mut out[..layout.byte_length];
+    let mut stats = Vec::with_capacity(outputs.len());
     let written = unsafe {
-        cpp::decode_mesh_direct_write(
-            data.as_ptr(),
-            data.len(),
-            out_buf.as_mut_ptr(),
-            out_buf.len(),
+        cpp::write_geometry(
+            geometry,
+            out.as_mut_ptr(),
+            out.len(),
+            &outputs,
+            config.output_mode() == OutputMode::PerCorner,
+            config.index_format() == IndexFormat::U32,
+            &mut stats,
         )
     };
-    if written == 0 || written > out_buf.len() {
-        return None;
+    if !written {
+        return Err(DecodeError::InvalidBitstream);
     }
-    out_buf.truncate(written);
-    #[cfg(feature = "perf")]
-    println!("decode_mesh_native took {:?}", start.elapsed());
+    let stats = stats
+        .into_iter()
+        .map(|stats| {
+            AttributeStats::from_sums(stats.min, stats.max, stats.sum, config.vertex_count())
+        })
+        .collect();
+    Ok(MeshStats::new(config, stats))
+}
+
+pub async fn decode_mesh_native(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    timings: &mut DecodeTimings,
+) -> Result<(Vec<u8>, MeshStats), DecodeError> {
+    trace_span!("decode_mesh", input_bytes = data.len());
+    let geometry = decode_for_config(data, config, false, 0, timings)?;
+    let mut stopwatch = Stopwatch::start();
+    let decoded = write_mesh(&geometry, config);
+    timings.write += stopwatch.lap();
+    decoded
+}
+
+/// Like [`decode_mesh_native`], writing into `out` instead of a new buffer. Also decodes
+/// point clouds if `config` has no indices.
+pub async fn decode_mesh_into_native(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    out: &mut [u8],
+) -> Result<MeshStats, DecodeError> {
+    let needed = config.checked_output_size()?;
+    if out.len() < needed {
+        return Err(DecodeError::OutputTooSmall {
+            needed,
+            found: out.len(),
+        });
+    }
+    trace_span!("decode_mesh_into", input_bytes = data.len());
+    let mut timings = DecodeTimings::default();
+    let geometry = decode_for_config(data, config, config.index_count() == 0, 0, &mut timings)?;
+    write_mesh_into(&geometry, config, out)
+}
+
+/// Decodes a mesh, or any geometry with `accept_point_cloud`, and checks it against the
+/// limits and attributes of `config`, adding the time of each phase to `timings`. See
+/// [`cpp::DecodeOptions`] for `skip_transforms`.
+pub fn decode_for_config(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    accept_point_cloud: bool,
+    skip_transforms: u32,
+    timings: &mut DecodeTimings,
+) -> Result<cxx::UniquePtr<cpp::DracoGeometry>, DecodeError> {
+    config.checked_output_size()?;
 
-    Some(out_buf)
+    let mut stopwatch = Stopwatch::start();
+    {
+        trace_span!("header");
+        let header = inspect_header(data).ok_or(DecodeError::InvalidBitstream)?;
+        if !accept_point_cloud && header.geometry_type() != GeometryType::TriangularMesh {
+            return Err(DecodeError::UnexpectedGeometryType);
+        }
+    }
+    timings.header += stopwatch.lap();
+
+    // Draco parses the header and metadata again before the stages it reports.
+    let mut timer = StageTimer::default();
+    let (geometry, info) = {
+        trace_span!("decode_geometry", input_bytes = data.len());
+        if accept_point_cloud {
+            decode_any_geometry(data, config.limits(), skip_transforms, &mut timer)?
+        } else {
+            decode_mesh_geometry(data, config.limits(), skip_transforms, &mut timer)?
+        }
+    };
+    let stages = timer.connectivity + timer.attributes;
+    timings.header += stopwatch.lap().saturating_sub(stages);
+    timings.connectivity += timer.connectivity;
+    timings.attributes += timer.attributes;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        points = info.num_points,
+        faces = info.num_faces,
+        attributes = info.attributes.len(),
+        "decoded draco geometry"
+    );
+
+    trace_span!("validate", attributes = config.attribute_slice().len());
+    let found = found_attributes(&info);
+    let vertex_count = match config.output_mode() {
+        OutputMode::Indexed => info.num_points,
+        OutputMode::PerCorner => info.num_faces * 3,
+    };
+    validate_config(config, vertex_count, info.num_faces * 3, &found)?;
+    check_dims(&info, attribute_sources(config, &found))?;
+    timings.validate += stopwatch.lap();
+    Ok(geometry)
+}
+
+/// Decodes every attribute of the mesh, in its own data type where this crate can represent
+/// it and as `Float32` otherwise.
+pub fn decode_mesh_auto_native(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<DecodedMesh, DecodeError> {
+    let (geometry, info) = decode_mesh_geometry(data, limits, 0, &mut StageTimer::default())?;
+    decode_auto(&geometry, &info, limits)
+}
+
+/// Like [`decode_mesh_auto_native`] but also accepts point clouds, which decode without
+/// indices. Returns the Draco unique id of every attribute alongside the mesh.
+pub fn decode_geometry_auto_native(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<(DecodedMesh, Vec<u32>), DecodeError> {
+    let (geometry, info) = decode_any_geometry(data, limits, 0, &mut StageTimer::default())?;
+    let mesh = decode_auto(&geometry, &info, limits)?;
+    let unique_ids = info.attributes.iter().map(|attr| attr.unique_id).collect();
+    Ok((mesh, unique_ids))
+}
+
+fn decode_auto(
+    geometry: &cpp::DracoGeometry,
+    info: &cpp::GeometryInfo,
+    limits: &DecodeLimits,
+) -> Result<DecodedMesh, DecodeError> {
+    check_dims(info, (0..info.attributes.len()).map(Some))?;
+    let mut config = DracoDecodeConfig::new(info.num_points, info.num_faces * 3);
+    config.set_limits(*limits);
+    let mut sets: HashMap<i32, u32> = HashMap::new();
+    for attr in &info.attributes {
+        let set = sets.entry(attr.attribute_type).or_default();
+        let semantic = AttributeSemantic::from_draco(attr.attribute_type, *set);
+        *set += 1;
+        let data_type =
+            AttributeDataType::from_draco(attr.data_type).unwrap_or(AttributeDataType::Float32);
+        config.add_semantic_attribute(semantic, attr.num_components, data_type);
+        config.set_attribute_normalized(config.attribute_slice().len() - 1, attr.normalized);
+    }
+    config.checked_output_size()?;
+
+    let (out_buf, stats) = write_mesh(geometry, &config)?;
+    Ok(DecodedMesh::new(out_buf, config).with_stats(stats))
+}
+
+pub fn decode_connectivity_native(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<ConnectivityMesh, DecodeError> {
+    if inspect_header(data)
+        .ok_or(DecodeError::InvalidBitstream)?
+        .geometry_type()
+        != GeometryType::TriangularMesh
+    {
+        return Err(DecodeError::UnexpectedGeometryType);
+    }
+    check_encoded_counts(data, limits)?;
+    let mut indices = Vec::new();
+    let mut opposites = Vec::new();
+    let mut positions = Vec::new();
+    if !cpp::decode_connectivity(data, &mut indices, &mut opposites, &mut positions) {
+        return Err(DecodeError::InvalidBitstream);
+    }
+    check_counts(
+        limits,
+        (positions.len() / 3) as u32,
+        (indices.len() / 3) as u32,
+    )?;
+    let output_words = indices.len() + opposites.len() + positions.len();
+    if !limits.allows_output_bytes(output_words * 4) {
+        return Err(DecodeError::LimitExceeded);
+    }
+    let positions = positions
+        .chunks_exact(3)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    Ok(ConnectivityMesh::new(indices, positions, opposites))
 }
 
 #[allow(dead_code)]
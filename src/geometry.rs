@@ -0,0 +1,249 @@
+//! Normals and tangents for decoded meshes that don't carry them. The generated attributes
+//! are appended to the mesh like decoded ones, see [`DecodedMesh::push_attribute`]. Lookups
+//! by semantic return the first match, so existing normals or tangents take precedence.
+
+use std::fmt;
+
+use crate::{AttributeDataType, AttributeSemantic, DecodedMesh};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeometryError {
+    /// The mesh has no 3 component position (or normal, or 2 component `TexCoord(0)` for
+    /// tangents).
+    MissingAttribute(AttributeSemantic),
+    /// An index points past the last vertex.
+    InvalidIndex,
+    /// The values passed to [`DecodedMesh::push_attribute`] don't cover every vertex.
+    InvalidLength,
+    /// MikkTSpace rejected the geometry, e.g. because it has no faces.
+    TangentGeneration,
+    /// An attribute has a component count the operation can't handle, e.g. joints that
+    /// aren't 4 per vertex.
+    UnsupportedDim {
+        semantic: AttributeSemantic,
+        dim: u32,
+    },
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::MissingAttribute(semantic) => {
+                write!(f, "mesh has no usable {semantic:?} attribute")
+            }
+            GeometryError::InvalidIndex => write!(f, "index out of range"),
+            GeometryError::InvalidLength => write!(f, "attribute length doesn't match the mesh"),
+            GeometryError::TangentGeneration => write!(f, "failed to generate tangents"),
+            GeometryError::UnsupportedDim { semantic, dim } => {
+                write!(
+                    f,
+                    "unsupported {semantic:?} attribute with {dim} components"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// How face normals are weighted when they are summed into vertex normals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// By face area, larger faces dominate.
+    #[default]
+    Area,
+    /// By the angle of the face at the vertex, independent of how the surface is
+    /// triangulated.
+    Angle,
+}
+
+impl DecodedMesh {
+    /// Appends `Float32x3` smooth normals averaged from the faces around each vertex.
+    /// Vertices without a non-degenerate face get a zero normal. Returns the new attribute
+    /// index.
+    pub fn compute_smooth_normals(
+        &mut self,
+        weighting: NormalWeighting,
+    ) -> Result<usize, GeometryError> {
+        let (positions, indices) = self.triangles()?;
+        let mut normals = vec![[0.0f32; 3]; positions.len()];
+        for face in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[face[i] as usize]);
+            let face_normal = cross(sub(b, a), sub(c, a));
+            match weighting {
+                // The cross product's length is twice the face area.
+                NormalWeighting::Area => {
+                    for &vertex in face {
+                        normals[vertex as usize] = add(normals[vertex as usize], face_normal);
+                    }
+                }
+                NormalWeighting::Angle => {
+                    let face_normal = normalize(face_normal);
+                    let corners = [(a, b, c), (b, c, a), (c, a, b)];
+                    for (&vertex, (p, next, prev)) in face.iter().zip(corners) {
+                        let angle = angle_between(sub(next, p), sub(prev, p));
+                        let weighted = face_normal.map(|n| n * angle);
+                        normals[vertex as usize] = add(normals[vertex as usize], weighted);
+                    }
+                }
+            }
+        }
+        let normals: Vec<[f32; 3]> = normals.into_iter().map(normalize).collect();
+        self.push_attribute(
+            AttributeSemantic::Normal,
+            3,
+            AttributeDataType::Float32,
+            bytemuck::cast_slice(&normals),
+        )
+    }
+
+    /// Splits the vertices (see [`DecodedMesh::split_vertices`]) and appends `Float32x3`
+    /// face normals. Returns the new attribute index.
+    pub fn compute_flat_normals(&mut self) -> Result<usize, GeometryError> {
+        self.triangles()?;
+        self.split_vertices()?;
+        let (positions, _) = self.triangles()?;
+        let normals: Vec<[f32; 3]> = positions
+            .chunks_exact(3)
+            .flat_map(|face| {
+                let normal = normalize(cross(sub(face[1], face[0]), sub(face[2], face[0])));
+                [normal; 3]
+            })
+            .collect();
+        self.push_attribute(
+            AttributeSemantic::Normal,
+            3,
+            AttributeDataType::Float32,
+            bytemuck::cast_slice(&normals),
+        )
+    }
+
+    /// Appends `Float32x4` MikkTSpace tangents, with the bitangent sign in `w`, computed
+    /// from the position, normal and `TexCoord(0)` attributes. Returns the new attribute
+    /// index.
+    #[cfg(feature = "mikktspace")]
+    pub fn generate_tangents(&mut self) -> Result<usize, GeometryError> {
+        let (positions, indices) = self.triangles()?;
+        let normals = self
+            .normals::<[f32; 3]>()
+            .ok_or(GeometryError::MissingAttribute(AttributeSemantic::Normal))?;
+        let uvs = self
+            .uvs::<[f32; 2]>()
+            .ok_or(GeometryError::MissingAttribute(
+                AttributeSemantic::TexCoord(0),
+            ))?;
+
+        let mut geometry = MikktspaceGeometry {
+            positions: &positions,
+            normals: &normals,
+            uvs: &uvs,
+            indices: &indices,
+            tangents: vec![[0.0; 4]; positions.len()],
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            return Err(GeometryError::TangentGeneration);
+        }
+        let tangents = geometry.tangents;
+        self.push_attribute(
+            AttributeSemantic::Tangent,
+            4,
+            AttributeDataType::Float32,
+            bytemuck::cast_slice(&tangents),
+        )
+    }
+
+    /// Positions and indices, checked to form whole triangles within the vertices.
+    fn triangles(&self) -> Result<(Vec<[f32; 3]>, Vec<u32>), GeometryError> {
+        let positions = self
+            .positions::<[f32; 3]>()
+            .ok_or(GeometryError::MissingAttribute(AttributeSemantic::Position))?
+            .into_owned();
+        let indices = self.indices();
+        if indices
+            .iter()
+            .any(|&index| index as usize >= positions.len())
+        {
+            return Err(GeometryError::InvalidIndex);
+        }
+        Ok((positions, indices))
+    }
+}
+
+#[cfg(feature = "mikktspace")]
+struct MikktspaceGeometry<'a> {
+    positions: &'a [[f32; 3]],
+    normals: &'a [[f32; 3]],
+    uvs: &'a [[f32; 2]],
+    indices: &'a [u32],
+    tangents: Vec<[f32; 4]>,
+}
+
+#[cfg(feature = "mikktspace")]
+impl MikktspaceGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.indices[face * 3 + vert] as usize
+    }
+}
+
+// Vertices shared between faces keep the tangent of the last face, as in Bevy.
+#[cfg(feature = "mikktspace")]
+impl bevy_mikktspace::Geometry for MikktspaceGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.vertex(face, vert)]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.vertex(face, vert)]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.uvs[self.vertex(face, vert)]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let vertex = self.vertex(face, vert);
+        self.tangents[vertex] = tangent;
+    }
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        v.map(|c| c / length)
+    } else {
+        [0.0; 3]
+    }
+}
+
+fn angle_between(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let cos = dot(normalize(a), normalize(b));
+    cos.clamp(-1.0, 1.0).acos()
+}
@@ -0,0 +1,727 @@
+//! Whole glTF meshes with `KHR_draco_mesh_compression`: every primitive and morph target of
+//! a mesh in one call, see [`GltfDocument::decode_mesh`].
+//!
+//! Morph target attributes compressed into the primitive's bitstream are listed in the
+//! extension's `targets`, one object per target mapping attribute names to unique ids like
+//! `attributes`, and their accessors have no `bufferView`. Other targets are read from their
+//! accessors, which have to follow the vertex order of the decoded primitive.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
+
+use base64::Engine;
+use serde_json::Value;
+
+use crate::mesh::read_f32s;
+use crate::{
+    AttributeDataType, AttributeSemantic, DecodeError, DecodeLimits, DecodedMesh,
+    DracoDecodeConfig, IndexFormat, MeshStats, try_decode_mesh,
+};
+
+pub(crate) const KHR_DRACO: &str = "KHR_draco_mesh_compression";
+pub(crate) const GLB_MAGIC: &[u8; 4] = b"glTF";
+pub(crate) const CHUNK_JSON: u32 = 0x4E4F_534A;
+pub(crate) const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// glTF's default primitive mode.
+const TRIANGLES: u32 = 4;
+
+#[derive(Debug)]
+pub enum GltfError {
+    /// Malformed JSON or GLB, or a reference that doesn't resolve.
+    Invalid(String),
+    /// Buffer `index` has an external uri, see [`GltfDocument::set_buffer`].
+    MissingBuffer(usize),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Invalid(reason) => write!(f, "invalid glTF: {reason}"),
+            GltfError::MissingBuffer(index) => write!(f, "glTF buffer {index} was not provided"),
+            GltfError::Decode(err) => write!(f, "failed to decode draco primitive: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<DecodeError> for GltfError {
+    fn from(err: DecodeError) -> Self {
+        GltfError::Decode(err)
+    }
+}
+
+pub(crate) fn invalid(reason: impl Into<String>) -> GltfError {
+    GltfError::Invalid(reason.into())
+}
+
+/// A decoded glTF mesh.
+#[derive(Debug)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+    /// Default morph target weights, empty when the mesh doesn't set them.
+    pub weights: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub struct GltfPrimitive {
+    /// Indices and base attributes, with semantics from the glTF attribute names. Primitives
+    /// without indices have none.
+    pub mesh: DecodedMesh,
+    pub material: Option<usize>,
+    /// The glTF topology, 4 for triangles.
+    pub mode: u32,
+    pub targets: Vec<MorphTarget>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MorphTarget {
+    pub attributes: Vec<MorphAttribute>,
+}
+
+impl MorphTarget {
+    pub fn get(&self, semantic: AttributeSemantic) -> Option<&MorphAttribute> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.semantic == semantic)
+    }
+
+    /// `POSITION` deltas, `None` if the target doesn't move positions.
+    pub fn positions(&self) -> Option<&[[f32; 3]]> {
+        self.deltas(AttributeSemantic::Position)
+    }
+
+    pub fn normals(&self) -> Option<&[[f32; 3]]> {
+        self.deltas(AttributeSemantic::Normal)
+    }
+
+    pub fn tangents(&self) -> Option<&[[f32; 3]]> {
+        self.deltas(AttributeSemantic::Tangent)
+    }
+
+    fn deltas(&self, semantic: AttributeSemantic) -> Option<&[[f32; 3]]> {
+        let attr = self.get(semantic)?;
+        (attr.dim == 3).then(|| bytemuck::cast_slice(&attr.deltas))
+    }
+}
+
+/// Per vertex deltas of one attribute, dequantized to `f32`.
+#[derive(Debug, Clone)]
+pub struct MorphAttribute {
+    pub semantic: AttributeSemantic,
+    pub dim: u32,
+    /// `dim` components per vertex.
+    pub deltas: Vec<f32>,
+}
+
+/// A parsed `.gltf` or `.glb` file with its buffers.
+#[derive(Debug, Clone)]
+pub struct GltfDocument {
+    json: Value,
+    buffers: Vec<Option<Vec<u8>>>,
+}
+
+impl GltfDocument {
+    /// Parses a `.gltf` or `.glb`. The GLB binary chunk and `data:` uris are resolved, other
+    /// buffers have to be provided with [`Self::set_buffer`].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, GltfError> {
+        let (json, blob) = parse_gltf(bytes)?;
+        let mut blob = blob;
+        let buffers = json["buffers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|buffer| match buffer["uri"].as_str() {
+                None => Ok(blob.take()),
+                Some(uri) if uri.starts_with("data:") => decode_data_uri(uri).map(Some),
+                Some(_) => Ok(None),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { json, buffers })
+    }
+
+    pub fn json(&self) -> &Value {
+        &self.json
+    }
+
+    /// The buffers [`Self::from_slice`] couldn't resolve, with their uri.
+    pub fn missing_buffers(&self) -> Vec<(usize, &str)> {
+        self.buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.is_none())
+            .map(|(index, _)| {
+                (
+                    index,
+                    self.json["buffers"][index]["uri"].as_str().unwrap_or(""),
+                )
+            })
+            .collect()
+    }
+
+    pub fn set_buffer(&mut self, index: usize, data: Vec<u8>) {
+        if let Some(buffer) = self.buffers.get_mut(index) {
+            *buffer = Some(data);
+        }
+    }
+
+    pub fn mesh_count(&self) -> usize {
+        self.json["meshes"].as_array().map_or(0, Vec::len)
+    }
+
+    /// Decodes every primitive and morph target of mesh `mesh`. Primitives sharing a
+    /// compressed bufferView are decoded once.
+    pub async fn decode_mesh(
+        &self,
+        mesh: usize,
+        limits: &DecodeLimits,
+    ) -> Result<GltfMesh, GltfError> {
+        self.decode_mesh_cached(mesh, limits, &mut HashMap::new())
+            .await
+    }
+
+    /// Decodes every mesh, sharing decoded bufferViews between primitives that decode the
+    /// same attributes from them.
+    pub async fn decode_meshes(&self, limits: &DecodeLimits) -> Result<Vec<GltfMesh>, GltfError> {
+        let mut decoded = HashMap::new();
+        let mut meshes = Vec::with_capacity(self.mesh_count());
+        for mesh in 0..self.mesh_count() {
+            meshes.push(self.decode_mesh_cached(mesh, limits, &mut decoded).await?);
+        }
+        Ok(meshes)
+    }
+
+    async fn decode_mesh_cached(
+        &self,
+        mesh: usize,
+        limits: &DecodeLimits,
+        decoded: &mut HashMap<DecodeKey, DecodedMesh>,
+    ) -> Result<GltfMesh, GltfError> {
+        let json = &self.json["meshes"]
+            .get(mesh)
+            .ok_or_else(|| invalid(format!("mesh {mesh} does not exist")))?;
+        let mut primitives = Vec::new();
+        for primitive in json["primitives"].as_array().into_iter().flatten() {
+            primitives.push(self.decode_primitive(primitive, limits, decoded).await?);
+        }
+        let weights = json["weights"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|weight| weight.as_f64().unwrap_or(0.0) as f32)
+            .collect();
+        Ok(GltfMesh {
+            name: json["name"].as_str().map(str::to_string),
+            primitives,
+            weights,
+        })
+    }
+
+    async fn decode_primitive(
+        &self,
+        primitive: &Value,
+        limits: &DecodeLimits,
+        decoded: &mut HashMap<DecodeKey, DecodedMesh>,
+    ) -> Result<GltfPrimitive, GltfError> {
+        let (mesh, mut compressed_targets) =
+            match primitive["extensions"][KHR_DRACO]["bufferView"].as_u64() {
+                Some(view) => {
+                    let (mut config, attributes) = primitive_config(&self.json, primitive)?;
+                    config.set_limits(*limits);
+                    let key = decode_key(view, &config);
+                    let mesh = match decoded.entry(key) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            let data =
+                                try_decode_mesh(self.buffer_view(view as usize)?, &config).await?;
+                            entry.insert(DecodedMesh::new(data, config))
+                        }
+                    };
+                    let (mesh, targets) = split(mesh, &self.json, &attributes)?;
+                    (mesh, Some(targets))
+                }
+                None => (self.read_primitive(primitive, limits)?, None),
+            };
+
+        let mut targets = Vec::new();
+        for (index, target) in primitive["targets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let mut compressed = compressed_targets
+                .as_mut()
+                .and_then(|targets| targets.get_mut(index));
+            let mut attributes = Vec::new();
+            for (name, accessor) in target.as_object().into_iter().flatten() {
+                let deltas = match compressed.as_mut().and_then(|target| target.remove(name)) {
+                    Some(deltas) => deltas,
+                    None => {
+                        let accessor = accessor
+                            .as_u64()
+                            .ok_or_else(|| invalid(format!("target {name} has no accessor")))?;
+                        // Checked before reading, the count could be anything.
+                        let count = self.json["accessors"][accessor as usize]["count"].as_u64();
+                        if count != Some(mesh.vertex_count().into()) {
+                            return Err(uncovered(name));
+                        }
+                        self.read_accessor(accessor as usize, limits)?
+                    }
+                };
+                let dim = deltas.dim;
+                if deltas.values.len() != dim as usize * mesh.vertex_count() as usize {
+                    return Err(uncovered(name));
+                }
+                attributes.push(MorphAttribute {
+                    semantic: AttributeSemantic::from_gltf(name),
+                    dim,
+                    deltas: deltas.values,
+                });
+            }
+            targets.push(MorphTarget { attributes });
+        }
+
+        Ok(GltfPrimitive {
+            mesh,
+            material: primitive["material"]
+                .as_u64()
+                .map(|material| material as usize),
+            mode: primitive["mode"]
+                .as_u64()
+                .map_or(TRIANGLES, |mode| mode as u32),
+            targets,
+        })
+    }
+
+    /// An uncompressed primitive, copied out of its accessors.
+    fn read_primitive(
+        &self,
+        primitive: &Value,
+        limits: &DecodeLimits,
+    ) -> Result<DecodedMesh, GltfError> {
+        let indices = match primitive["indices"].as_u64() {
+            Some(accessor) => {
+                let (bytes, _, data_type) = self.accessor_bytes(accessor as usize, limits)?;
+                read_indices(data_type, &bytes)?
+            }
+            None => Vec::new(),
+        };
+        let vertex_count = accessor_count(&self.json, &primitive["attributes"]["POSITION"])?;
+        if indices.iter().any(|&index| index >= vertex_count) {
+            return Err(invalid("primitive index out of range"));
+        }
+
+        let mut config = DracoDecodeConfig::new(vertex_count, indices.len() as u32);
+        config.set_limits(*limits);
+        // The format follows the index count, but indices can reach any vertex.
+        if vertex_count > u32::from(u16::MAX) {
+            config.set_index_format(IndexFormat::U32);
+        }
+        let mut data = match config.index_format() {
+            IndexFormat::U16 => {
+                let indices: Vec<u16> = indices.iter().map(|&index| index as u16).collect();
+                bytemuck::cast_slice(&indices).to_vec()
+            }
+            IndexFormat::U32 => bytemuck::cast_slice(&indices).to_vec(),
+        };
+        for (name, accessor) in primitive["attributes"].as_object().into_iter().flatten() {
+            let accessor = accessor
+                .as_u64()
+                .ok_or_else(|| invalid(format!("attribute {name} has no accessor")))?
+                as usize;
+            let (bytes, dim, data_type) = self.accessor_bytes(accessor, limits)?;
+            if bytes.len() != dim as usize * data_type.size_in_bytes() * vertex_count as usize {
+                return Err(invalid(format!(
+                    "attribute {name} doesn't cover every vertex"
+                )));
+            }
+            config.add_gltf_attribute(
+                name,
+                dim,
+                data_type,
+                accessor_normalized(&self.json, accessor),
+            );
+            data.extend_from_slice(&bytes);
+        }
+        if !limits.allows_output_bytes(data.len()) {
+            return Err(DecodeError::LimitExceeded.into());
+        }
+        let stats = MeshStats::compute(&data, &config);
+        let mesh = DecodedMesh::new(data, config);
+        Ok(match stats {
+            Some(stats) => mesh.with_stats(stats),
+            None => mesh,
+        })
+    }
+
+    fn buffer_view(&self, view: usize) -> Result<&[u8], GltfError> {
+        let view_json = &self.json["bufferViews"][view];
+        let buffer = view_json["buffer"]
+            .as_u64()
+            .ok_or_else(|| invalid(format!("bufferView {view} has no buffer")))?
+            as usize;
+        let data = self
+            .buffers
+            .get(buffer)
+            .ok_or_else(|| invalid(format!("buffer {buffer} does not exist")))?
+            .as_deref()
+            .ok_or(GltfError::MissingBuffer(buffer))?;
+        let offset = view_json["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let length = view_json["byteLength"].as_u64().unwrap_or(0) as usize;
+        offset
+            .checked_add(length)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid(format!("bufferView {view} out of range")))
+    }
+
+    fn read_accessor(&self, accessor: usize, limits: &DecodeLimits) -> Result<Deltas, GltfError> {
+        let (bytes, dim, data_type) = self.accessor_bytes(accessor, limits)?;
+        let normalized = accessor_normalized(&self.json, accessor);
+        Ok(Deltas {
+            dim,
+            values: read_f32s(data_type, &bytes, normalized),
+        })
+    }
+
+    /// The tightly packed values of `accessor` with its dim and component type. Accessors
+    /// without a bufferView are zeros, sparse values are applied on top. The packed size
+    /// counts against `limits`' output bytes.
+    fn accessor_bytes(
+        &self,
+        accessor: usize,
+        limits: &DecodeLimits,
+    ) -> Result<(Vec<u8>, u32, AttributeDataType), GltfError> {
+        let json = &self.json["accessors"][accessor];
+        let (Some(dim), Some(data_type), Some(count)) = (
+            accessor_dim(json),
+            accessor_data_type(json),
+            json["count"].as_u64(),
+        ) else {
+            return Err(invalid(format!("unsupported accessor {accessor}")));
+        };
+        let size = dim as usize * data_type.size_in_bytes();
+        let out_of_range = || invalid(format!("accessor {accessor} out of range"));
+        let count = usize::try_from(count).map_err(|_| out_of_range())?;
+        let len = count.checked_mul(size).ok_or(DecodeError::LimitExceeded)?;
+        if !limits.allows_output_bytes(len) {
+            return Err(DecodeError::LimitExceeded.into());
+        }
+
+        // Check the whole range is in the bufferView before allocating for it.
+        let source = match json["bufferView"].as_u64() {
+            Some(view) => {
+                let bytes = self.buffer_view(view as usize)?;
+                let stride = self.json["bufferViews"][view as usize]["byteStride"]
+                    .as_u64()
+                    .map_or(size, |stride| stride as usize);
+                let offset = json["byteOffset"].as_u64().unwrap_or(0) as usize;
+                let end = count.checked_sub(1).map_or(Some(offset), |last| {
+                    last.checked_mul(stride)?
+                        .checked_add(offset)?
+                        .checked_add(size)
+                });
+                if end.is_none_or(|end| end > bytes.len()) {
+                    return Err(out_of_range());
+                }
+                Some((&bytes[offset..], stride))
+            }
+            None => None,
+        };
+        let mut out = vec![0u8; len];
+        if let Some((bytes, stride)) = source {
+            for (i, value) in out.chunks_exact_mut(size).enumerate() {
+                let start = i * stride;
+                value.copy_from_slice(&bytes[start..start + size]);
+            }
+        }
+
+        let sparse = &json["sparse"];
+        if let Some(sparse_count) = sparse["count"].as_u64() {
+            let sparse_count = sparse_count as usize;
+            let section = |section: &Value, size: usize| -> Result<&[u8], GltfError> {
+                let view = section["bufferView"].as_u64().ok_or_else(out_of_range)?;
+                let offset = section["byteOffset"].as_u64().unwrap_or(0) as usize;
+                let bytes = self.buffer_view(view as usize)?;
+                sparse_count
+                    .checked_mul(size)
+                    .and_then(|len| offset.checked_add(len))
+                    .and_then(|end| bytes.get(offset..end))
+                    .ok_or_else(out_of_range)
+            };
+            let index_type = accessor_data_type(&sparse["indices"]).ok_or_else(out_of_range)?;
+            let indices = section(&sparse["indices"], index_type.size_in_bytes())?;
+            let values = section(&sparse["values"], size)?;
+            let indices = read_indices(index_type, indices)?;
+            for (index, value) in indices.into_iter().zip(values.chunks_exact(size)) {
+                let start = (index as usize)
+                    .checked_mul(size)
+                    .ok_or_else(out_of_range)?;
+                out.get_mut(start..)
+                    .and_then(|rest| rest.get_mut(..size))
+                    .ok_or_else(out_of_range)?
+                    .copy_from_slice(value);
+            }
+        }
+        Ok((out, dim, data_type))
+    }
+}
+
+struct Deltas {
+    dim: u32,
+    values: Vec<f32>,
+}
+
+/// The base mesh of a primitive decoded with its [`primitive_config`], and the deltas of its
+/// compressed targets by name.
+fn split(
+    decoded: &DecodedMesh,
+    json: &Value,
+    attributes: &[DracoAttribute<'_>],
+) -> Result<(DecodedMesh, Vec<HashMap<String, Deltas>>), GltfError> {
+    let source = decoded.config();
+    let mut config = DracoDecodeConfig::new(source.vertex_count(), source.index_count());
+    config.set_limits(*source.limits());
+    let mut data = decoded.index_bytes().to_vec();
+    let mut targets: Vec<HashMap<String, Deltas>> = Vec::new();
+
+    for (index, attr) in attributes.iter().enumerate() {
+        let decoded_attr = source.attribute_slice()[index];
+        let bytes = decoded
+            .packed_attribute_bytes(index)
+            .ok_or_else(|| invalid(format!("draco attribute {} was not decoded", attr.name)))?;
+        let normalized = accessor_normalized(json, attr.accessor);
+        match attr.target {
+            None => {
+                config.add_gltf_attribute(
+                    attr.name,
+                    decoded_attr.dim(),
+                    decoded_attr.data_type(),
+                    normalized,
+                );
+                data.extend_from_slice(&bytes);
+            }
+            Some(target) => {
+                if targets.len() <= target {
+                    targets.resize_with(target + 1, HashMap::new);
+                }
+                let deltas = Deltas {
+                    dim: decoded_attr.dim(),
+                    values: read_f32s(decoded_attr.data_type(), &bytes, normalized),
+                };
+                targets[target].insert(attr.name.to_string(), deltas);
+            }
+        }
+    }
+
+    let stats = MeshStats::compute(&data, &config);
+    let mesh = DecodedMesh::new(data, config);
+    let mesh = match stats {
+        Some(stats) => mesh.with_stats(stats),
+        None => mesh,
+    };
+    Ok((mesh, targets))
+}
+
+/// Splits a GLB into its JSON and BIN chunks, or parses `bytes` as plain glTF JSON.
+pub(crate) fn parse_gltf(bytes: &[u8]) -> Result<(Value, Option<Vec<u8>>), GltfError> {
+    if !bytes.starts_with(GLB_MAGIC) {
+        let json = serde_json::from_slice(bytes).map_err(|err| invalid(err.to_string()))?;
+        return Ok((json, None));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().unwrap());
+        let chunk = bytes
+            .get(pos + 8..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or_else(|| invalid("truncated GLB chunk"))?;
+        match kind {
+            CHUNK_JSON => {
+                json = Some(serde_json::from_slice(chunk).map_err(|err| invalid(err.to_string()))?)
+            }
+            CHUNK_BIN => bin = Some(chunk.to_vec()),
+            _ => {}
+        }
+        pos += 8 + len;
+    }
+    Ok((json.ok_or_else(|| invalid("GLB without JSON chunk"))?, bin))
+}
+
+pub(crate) fn decode_data_uri(uri: &str) -> Result<Vec<u8>, GltfError> {
+    let (_, data) = uri
+        .split_once(";base64,")
+        .ok_or_else(|| invalid("data uri buffer is not base64"))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|err| invalid(err.to_string()))
+}
+
+/// Index accessor values widened to `u32`.
+fn read_indices(data_type: AttributeDataType, bytes: &[u8]) -> Result<Vec<u32>, GltfError> {
+    match data_type {
+        AttributeDataType::UInt8 => Ok(bytes.iter().map(|&index| u32::from(index)).collect()),
+        AttributeDataType::UInt16 => Ok(bytemuck::pod_collect_to_vec::<u8, u16>(bytes)
+            .into_iter()
+            .map(u32::from)
+            .collect()),
+        AttributeDataType::UInt32 => Ok(bytemuck::pod_collect_to_vec(bytes)),
+        _ => Err(invalid("indices are not unsigned integers")),
+    }
+}
+
+pub(crate) fn accessor_dim(accessor: &Value) -> Option<u32> {
+    match accessor["type"].as_str()? {
+        "SCALAR" => Some(1),
+        "VEC2" => Some(2),
+        "VEC3" => Some(3),
+        "VEC4" => Some(4),
+        _ => None,
+    }
+}
+
+pub(crate) fn accessor_data_type(accessor: &Value) -> Option<AttributeDataType> {
+    match accessor["componentType"].as_u64()? {
+        5120 => Some(AttributeDataType::Int8),
+        5121 => Some(AttributeDataType::UInt8),
+        5122 => Some(AttributeDataType::Int16),
+        5123 => Some(AttributeDataType::UInt16),
+        5125 => Some(AttributeDataType::UInt32),
+        5126 => Some(AttributeDataType::Float32),
+        _ => None,
+    }
+}
+
+fn accessor_normalized(json: &Value, accessor: usize) -> bool {
+    json["accessors"][accessor]["normalized"]
+        .as_bool()
+        .unwrap_or(false)
+}
+
+fn accessor_count(json: &Value, accessor: &Value) -> Result<u32, GltfError> {
+    accessor
+        .as_u64()
+        .and_then(|index| json["accessors"][index as usize]["count"].as_u64())
+        .and_then(|count| u32::try_from(count).ok())
+        .ok_or_else(|| invalid("primitive without accessor count"))
+}
+
+fn uncovered(target: &str) -> GltfError {
+    invalid(format!("target {target} doesn't cover every vertex"))
+}
+
+/// An attribute stored in a compressed primitive's bitstream.
+pub(crate) struct DracoAttribute<'a> {
+    pub unique_id: u32,
+    pub name: &'a str,
+    pub accessor: usize,
+    /// The morph target, `None` for the primitive's own attributes.
+    pub target: Option<usize>,
+}
+
+/// The config matching a compressed primitive, which selects its attributes and those of its
+/// compressed morph targets by unique id, in unique id order, and those attributes in the
+/// same order.
+pub(crate) fn primitive_config<'a>(
+    json: &Value,
+    primitive: &'a Value,
+) -> Result<(DracoDecodeConfig, Vec<DracoAttribute<'a>>), GltfError> {
+    let extension = &primitive["extensions"][KHR_DRACO];
+    let vertex_count = accessor_count(json, &primitive["attributes"]["POSITION"])?;
+    // Points and other primitives without indices have none to decode.
+    let index_count = match primitive["indices"] {
+        Value::Null => 0,
+        ref indices => accessor_count(json, indices)?,
+    };
+
+    let ids = |map: &'a Value,
+               accessors: &'a Value,
+               target: Option<usize>|
+     -> Result<Vec<DracoAttribute<'a>>, GltfError> {
+        map.as_object()
+            .into_iter()
+            .flatten()
+            .map(|(name, id)| {
+                let unique_id = id
+                    .as_u64()
+                    .and_then(|id| u32::try_from(id).ok())
+                    .ok_or_else(|| invalid(format!("draco attribute {name} has no unique id")))?;
+                let accessor = accessors[name.as_str()]
+                    .as_u64()
+                    .ok_or_else(|| invalid(format!("draco attribute {name} has no accessor")))?
+                    as usize;
+                Ok(DracoAttribute {
+                    unique_id,
+                    name,
+                    accessor,
+                    target,
+                })
+            })
+            .collect()
+    };
+
+    if !extension["attributes"].is_object() {
+        return Err(invalid("draco extension without attributes"));
+    }
+    let mut attributes = ids(&extension["attributes"], &primitive["attributes"], None)?;
+    for (target, map) in extension["targets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        attributes.extend(ids(map, &primitive["targets"][target], Some(target))?);
+    }
+    attributes.sort_by_key(|attr| attr.unique_id);
+
+    let mut config = DracoDecodeConfig::new(vertex_count, index_count);
+    for (index, attr) in attributes.iter().enumerate() {
+        let accessor = &json["accessors"][attr.accessor];
+        let (Some(dim), Some(data_type)) = (accessor_dim(accessor), accessor_data_type(accessor))
+        else {
+            return Err(invalid(format!("unsupported accessor for {}", attr.name)));
+        };
+        let normalized = accessor_normalized(json, attr.accessor);
+        if attr.target.is_none() {
+            config.add_gltf_attribute(attr.name, dim, data_type, normalized);
+        } else {
+            config.add_attribute(dim, data_type);
+            config.set_attribute_normalized(index, normalized);
+        }
+        config.set_attribute_unique_id(index, attr.unique_id);
+    }
+    Ok((config, attributes))
+}
+
+/// A compressed bufferView and everything its decoded data depends on. Primitives sharing
+/// the view only share the decoded data if they decode it the same way.
+pub(crate) type DecodeKey = (u64, u32, u32, Vec<(Option<u32>, u32, i32, bool)>);
+
+pub(crate) fn decode_key(view: u64, config: &DracoDecodeConfig) -> DecodeKey {
+    let attributes = config
+        .attribute_slice()
+        .iter()
+        .map(|attr| {
+            (
+                attr.unique_id(),
+                attr.dim(),
+                attr.data_type().to_draco(),
+                attr.normalized(),
+            )
+        })
+        .collect();
+    (
+        view,
+        config.vertex_count(),
+        config.index_count(),
+        attributes,
+    )
+}
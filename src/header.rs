@@ -0,0 +1,219 @@
+const DRACO_MAGIC: &[u8; 5] = b"DRACO";
+const METADATA_FLAG_MASK: u16 = 0x8000;
+
+pub(crate) const HEADER_SIZE: usize = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryType {
+    PointCloud,
+    TriangularMesh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderMethod {
+    Sequential,
+    KdTree,
+    Edgebreaker,
+}
+
+/// The fixed-size header every Draco bitstream starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DracoHeader {
+    version_major: u8,
+    version_minor: u8,
+    geometry_type: GeometryType,
+    encoder_method: EncoderMethod,
+    flags: u16,
+}
+
+impl DracoHeader {
+    pub fn version(&self) -> (u8, u8) {
+        (self.version_major, self.version_minor)
+    }
+
+    pub fn geometry_type(&self) -> GeometryType {
+        self.geometry_type
+    }
+
+    pub fn encoder_method(&self) -> EncoderMethod {
+        self.encoder_method
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn has_metadata(&self) -> bool {
+        self.flags & METADATA_FLAG_MASK != 0
+    }
+}
+
+/// Parses the Draco header without decoding anything else, so untrusted input can be
+/// rejected before it reaches the native decoder. Returns `None` for anything that is
+/// not a Draco bitstream this crate can decode.
+pub fn inspect_header(data: &[u8]) -> Option<DracoHeader> {
+    if data.len() < HEADER_SIZE || &data[..5] != DRACO_MAGIC {
+        return None;
+    }
+
+    let version_major = data[5];
+    let version_minor = data[6];
+    // Draco only ever shipped 1.x and 2.x bitstreams.
+    if !(1..=2).contains(&version_major) {
+        return None;
+    }
+
+    let geometry_type = match data[7] {
+        0 => GeometryType::PointCloud,
+        1 => GeometryType::TriangularMesh,
+        _ => return None,
+    };
+    let encoder_method = match (geometry_type, data[8]) {
+        (_, 0) => EncoderMethod::Sequential,
+        (GeometryType::PointCloud, 1) => EncoderMethod::KdTree,
+        (GeometryType::TriangularMesh, 1) => EncoderMethod::Edgebreaker,
+        _ => return None,
+    };
+    let flags = u16::from_le_bytes([data[9], data[10]]);
+
+    Some(DracoHeader {
+        version_major,
+        version_minor,
+        geometry_type,
+        encoder_method,
+        flags,
+    })
+}
+
+/// The point and face counts a bitstream declares ahead of its connectivity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EncodedCounts {
+    /// Exact for point clouds and sequential meshes. Edgebreaker meshes declare their
+    /// encoded vertices, which attribute seams split into more points, so this is only a
+    /// lower bound for them.
+    pub points: u32,
+    pub faces: u32,
+}
+
+/// Reads the counts Draco allocates the geometry by, skipping the metadata, without
+/// decoding the bitstream.
+pub(crate) fn encoded_counts(data: &[u8]) -> Option<EncodedCounts> {
+    let header = inspect_header(data)?;
+    let version = header.version();
+    let mut reader = Reader {
+        data,
+        pos: HEADER_SIZE,
+    };
+    if header.has_metadata() && version >= (1, 3) {
+        reader.skip_metadata()?;
+    }
+
+    let (points, faces) = match (header.geometry_type(), header.encoder_method()) {
+        (GeometryType::PointCloud, _) => (u32::try_from(reader.i32()?).ok()?, 0),
+        (GeometryType::TriangularMesh, EncoderMethod::Sequential) if version < (2, 2) => {
+            let faces = reader.u32()?;
+            (reader.u32()?, faces)
+        }
+        (GeometryType::TriangularMesh, EncoderMethod::Sequential) => {
+            let faces = reader.varint()?;
+            (reader.varint()?, faces)
+        }
+        (GeometryType::TriangularMesh, _) => {
+            // Traversal decoder type.
+            reader.u8()?;
+            let mut count = || {
+                if version < (2, 0) {
+                    reader.u32()
+                } else {
+                    reader.varint()
+                }
+            };
+            if version < (2, 2) {
+                // New vertices, only used by old bitstreams.
+                count()?;
+            }
+            let points = count()?;
+            (points, count()?)
+        }
+    };
+    Some(EncodedCounts { points, faces })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Draco's LEB128 varint, at most 5 bytes for a `u32`.
+    fn varint(&mut self) -> Option<u32> {
+        let mut value = 0u64;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return u32::try_from(value).ok();
+            }
+        }
+        None
+    }
+
+    /// Skips the attribute metadata and the nested geometry metadata. Every metadata is
+    /// its entries followed by its children, each of which starts with a name.
+    fn skip_metadata(&mut self) -> Option<()> {
+        let attributes = self.varint()?;
+        for _ in 0..attributes {
+            // Attribute unique id.
+            self.varint()?;
+            self.skip_metadata_tree()?;
+        }
+        self.skip_metadata_tree()
+    }
+
+    fn skip_metadata_tree(&mut self) -> Option<()> {
+        let mut pending = 1u64;
+        let mut root = true;
+        while pending > 0 {
+            pending -= 1;
+            if !root {
+                self.skip_name()?;
+            }
+            root = false;
+            for _ in 0..self.varint()? {
+                self.skip_name()?;
+                let len = self.varint()?;
+                self.bytes(len as usize)?;
+            }
+            let children = self.varint()?;
+            // Every child takes at least a byte, as Draco checks.
+            if children as usize > self.data.len() - self.pos {
+                return None;
+            }
+            pending += u64::from(children);
+        }
+        Some(())
+    }
+
+    fn skip_name(&mut self) -> Option<()> {
+        let len = self.u8()?;
+        self.bytes(len.into()).map(|_| ())
+    }
+}
@@ -0,0 +1,244 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{AttributeDataType, DracoDecodeConfig};
+
+/// Width of the indices at the start of the decoded buffer. Indices are `u16` whenever
+/// the index count fits, `u32` otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IndexFormat {
+    U16,
+    U32,
+}
+
+impl IndexFormat {
+    pub fn for_index_count(index_count: u32) -> Self {
+        if index_count <= u16::MAX as u32 {
+            IndexFormat::U16
+        } else {
+            IndexFormat::U32
+        }
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            IndexFormat::U16 => 2,
+            IndexFormat::U32 => 4,
+        }
+    }
+}
+
+/// How attributes follow the indices in the decoded buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VertexLayout {
+    /// One tightly packed block per attribute, in attribute order.
+    #[default]
+    Planar,
+    /// A single block of vertices. Each attribute starts at a 4 byte aligned offset
+    /// within the vertex and the vertex stride is a multiple of 4, as GPU APIs require.
+    Interleaved,
+}
+
+/// Which vertices attributes are written for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OutputMode {
+    /// One vertex per Draco point, shared by the faces that use it.
+    #[default]
+    Indexed,
+    /// Three vertices per face with the values of each corner, e.g. for flat shading or
+    /// lightmap UVs. The vertex count is the index count and the indices are sequential.
+    PerCorner,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexLayout {
+    pub format: IndexFormat,
+    pub count: usize,
+    pub byte_range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttributeLayout {
+    pub data_type: AttributeDataType,
+    pub normalized: bool,
+    /// Components per element, e.g. 3 for a position.
+    pub component_count: usize,
+    /// Elements in the attribute, one per vertex.
+    pub element_count: usize,
+    /// Bytes between consecutive elements, the element size for planar output and the
+    /// vertex size for interleaved output.
+    pub stride: usize,
+    /// From the first byte of the first element to the last byte of the last element.
+    pub byte_range: Range<usize>,
+}
+
+impl AttributeLayout {
+    pub fn element_size(&self) -> usize {
+        self.component_count * self.data_type.size_in_bytes()
+    }
+}
+
+/// Where everything lives in the buffer returned by [`crate::decode_mesh`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BufferLayout {
+    pub indices: IndexLayout,
+    pub vertex_layout: VertexLayout,
+    /// Everything after the indices that holds attribute data.
+    pub vertex_range: Range<usize>,
+    pub attributes: Vec<AttributeLayout>,
+    pub byte_length: usize,
+}
+
+impl BufferLayout {
+    pub fn new(config: &DracoDecodeConfig) -> Self {
+        Self::with_vertex_layout(config, config.vertex_layout())
+    }
+
+    /// Offsets saturate for configs whose buffer can't be addressed, which decoding rejects,
+    /// see [`DracoDecodeConfig::checked_buffer_size`].
+    pub fn with_vertex_layout(config: &DracoDecodeConfig, vertex_layout: VertexLayout) -> Self {
+        let format = config.index_format();
+        let count = config.index_count() as usize;
+        let index_end = count.saturating_mul(format.size_in_bytes());
+        let indices = IndexLayout {
+            format,
+            count,
+            byte_range: 0..index_end,
+        };
+
+        let element_count = config.vertex_count() as usize;
+        let element_sizes = config
+            .attribute_slice()
+            .iter()
+            .map(|attr| (attr.dim() as usize).saturating_mul(attr.data_type().size_in_bytes()));
+        let (vertex_start, offsets, strides): (usize, Vec<usize>, Vec<usize>) = match vertex_layout
+        {
+            VertexLayout::Planar => {
+                let mut offset = index_end;
+                let (offsets, strides) = element_sizes
+                    .map(|size| {
+                        let start = offset;
+                        offset = offset.saturating_add(size.saturating_mul(element_count));
+                        (start, size)
+                    })
+                    .unzip();
+                (index_end, offsets, strides)
+            }
+            VertexLayout::Interleaved => {
+                let vertex_start = index_end.checked_next_multiple_of(4).unwrap_or(usize::MAX);
+                let mut vertex_size: usize = 0;
+                let offsets: Vec<usize> = element_sizes
+                    .map(|size| {
+                        let start = vertex_size;
+                        let size = size.checked_next_multiple_of(4).unwrap_or(usize::MAX);
+                        vertex_size = vertex_size.saturating_add(size);
+                        vertex_start.saturating_add(start)
+                    })
+                    .collect();
+                let strides = vec![vertex_size; offsets.len()];
+                (vertex_start, offsets, strides)
+            }
+        };
+
+        let attributes: Vec<AttributeLayout> = config
+            .attribute_slice()
+            .iter()
+            .zip(offsets.into_iter().zip(strides))
+            .map(|(attr, (start, stride))| {
+                let component_count = attr.dim() as usize;
+                let element_size = component_count.saturating_mul(attr.data_type().size_in_bytes());
+                let end = match element_count {
+                    0 => start,
+                    n => start
+                        .saturating_add((n - 1).saturating_mul(stride))
+                        .saturating_add(element_size),
+                };
+                AttributeLayout {
+                    data_type: attr.data_type(),
+                    normalized: attr.normalized(),
+                    component_count,
+                    element_count,
+                    stride,
+                    byte_range: start..end,
+                }
+            })
+            .collect();
+
+        let vertex_end = match vertex_layout {
+            VertexLayout::Planar => attributes
+                .last()
+                .map_or(vertex_start, |attr| attr.byte_range.end),
+            VertexLayout::Interleaved => {
+                let stride = attributes.first().map_or(0, |attr| attr.stride);
+                vertex_start.saturating_add(stride.saturating_mul(element_count))
+            }
+        };
+
+        Self {
+            indices,
+            vertex_layout,
+            vertex_range: vertex_start..vertex_end,
+            attributes,
+            byte_length: vertex_end,
+        }
+    }
+
+    /// Copies `buf`, laid out as `from`, into a new buffer laid out as `self`, e.g. planar
+    /// output into an interleaved buffer. Both layouts must describe the same config, up to
+    /// the vertex layout and index format.
+    pub fn repack(&self, from: &BufferLayout, buf: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; self.byte_length];
+        let indices = &buf[from.indices.byte_range.clone()];
+        let out_indices = &mut out[self.indices.byte_range.clone()];
+        match (from.indices.format, self.indices.format) {
+            (IndexFormat::U16, IndexFormat::U32) => {
+                for (src, dst) in indices.chunks_exact(2).zip(out_indices.chunks_exact_mut(4)) {
+                    let index = u16::from_le_bytes([src[0], src[1]]) as u32;
+                    dst.copy_from_slice(&index.to_le_bytes());
+                }
+            }
+            (IndexFormat::U32, IndexFormat::U16) => {
+                for (src, dst) in indices.chunks_exact(4).zip(out_indices.chunks_exact_mut(2)) {
+                    let index = u32::from_le_bytes([src[0], src[1], src[2], src[3]]) as u16;
+                    dst.copy_from_slice(&index.to_le_bytes());
+                }
+            }
+            _ => out_indices.copy_from_slice(indices),
+        }
+        for (src, dst) in from.attributes.iter().zip(&self.attributes) {
+            let size = dst.element_size();
+            for i in 0..dst.element_count {
+                let from = src.byte_range.start + i * src.stride;
+                let to = dst.byte_range.start + i * dst.stride;
+                out[to..to + size].copy_from_slice(&buf[from..from + size]);
+            }
+        }
+        out
+    }
+
+    /// The index bytes of a decoded buffer, `None` if `buf` is shorter than the layout.
+    pub fn index_bytes<'a>(&self, buf: &'a [u8]) -> Option<&'a [u8]> {
+        buf.get(self.indices.byte_range.clone())
+    }
+
+    /// The bytes of attribute `index` in a decoded buffer. For interleaved output elements
+    /// are [`AttributeLayout::stride`] bytes apart.
+    pub fn attribute_bytes<'a>(&self, buf: &'a [u8], index: usize) -> Option<&'a [u8]> {
+        let attr = self.attributes.get(index)?;
+        buf.get(attr.byte_range.clone())
+    }
+}
+
+impl From<&DracoDecodeConfig> for BufferLayout {
+    fn from(config: &DracoDecodeConfig) -> Self {
+        BufferLayout::new(config)
+    }
+}
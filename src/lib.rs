@@ -1,23 +1,177 @@
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod decoder;
+pub mod element;
+pub mod error;
 #[cfg(not(target_arch = "wasm32"))]
 mod ffi;
+pub mod geometry;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod header;
+pub mod layout;
+pub mod mesh;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mmap;
+mod skinning;
+pub mod stats;
+pub mod stream;
+#[cfg(feature = "tiles3d")]
+pub mod tiles3d;
+pub mod timing;
+pub mod topology;
 pub mod utils;
+pub mod validation;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
+#[cfg(feature = "wgpu")]
+pub mod wgpu;
 
 #[cfg(not(target_arch = "wasm32"))]
-use ffi::decode_mesh_native;
-pub use utils::{AttributeDataType, AttributeValues, DracoDecodeConfig, MeshAttribute};
+pub use decoder::{DecoderStats, DracoDecoder};
+pub use element::AttributeElement;
+pub use error::DecodeError;
+#[cfg(not(target_arch = "wasm32"))]
+use ffi::{
+    decode_connectivity_native, decode_geometry_auto_native, decode_mesh_auto_native,
+    decode_mesh_into_native, decode_mesh_native, decode_point_cloud_native,
+};
+pub use geometry::{GeometryError, NormalWeighting};
+pub use header::{DracoHeader, EncoderMethod, GeometryType, inspect_header};
+pub use layout::{
+    AttributeLayout, BufferLayout, IndexFormat, IndexLayout, OutputMode, VertexLayout,
+};
+pub use mesh::{AttributeSemantic, DecodedMesh};
+pub use stats::{Aabb, AttributeStats, BoundingSphere, MeshBounds, MeshStats};
+#[cfg(feature = "tokio")]
+pub use stream::decode_mesh_from_async_read;
+#[cfg(feature = "futures")]
+pub use stream::decode_mesh_from_futures_read;
+#[cfg(not(target_arch = "wasm32"))]
+pub use stream::decode_mesh_from_path;
+pub use stream::{DracoStreamDecoder, StreamError, StreamStatus, decode_mesh_from_reader};
+pub use timing::DecodeTimings;
+pub use topology::{ConnectivityMesh, CornerTable};
+pub use utils::{
+    AttributeDataType, AttributeValues, DecodeLimits, DracoDecodeConfig, MeshAttribute,
+};
+pub use validation::{ConfigMismatch, ValidationMode};
 #[cfg(target_arch = "wasm32")]
 use wasm::decode_mesh_wasm_worker;
 
-#[cfg(not(target_arch = "wasm32"))]
 pub async fn decode_mesh(data: &[u8], config: &DracoDecodeConfig) -> Option<Vec<u8>> {
-    decode_mesh_native(data, config).await
+    try_decode_mesh(data, config).await.ok()
+}
+
+/// Like [`decode_mesh`], but reports why decoding failed, including every way `config`
+/// disagrees with the bitstream (see [`DracoDecodeConfig::set_validation`]).
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn try_decode_mesh(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+) -> Result<Vec<u8>, DecodeError> {
+    decode_mesh_native(data, config, &mut DecodeTimings::default())
+        .await
+        .map(|(buf, _)| buf)
 }
 
 #[cfg(target_arch = "wasm32")]
-pub async fn decode_mesh(data: &[u8], config: &DracoDecodeConfig) -> Option<Vec<u8>> {
-    decode_mesh_wasm_worker(data, config).await
+pub async fn try_decode_mesh(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+) -> Result<Vec<u8>, DecodeError> {
+    decode_mesh_wasm_worker(data, config, &mut DecodeTimings::default()).await
+}
+
+/// Like [`try_decode_mesh`], returning the buffer with its config and [`MeshStats`]. Natively
+/// the stats are gathered while the attributes are written, on wasm in a pass over the
+/// worker's output.
+pub async fn decode_mesh_with_stats(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+) -> Result<DecodedMesh, DecodeError> {
+    decode_mesh_with_timings(data, config)
+        .await
+        .map(|(mesh, _)| mesh)
+}
+
+/// Like [`decode_mesh_with_stats`], also returning how long each phase of the decode took.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_mesh_with_timings(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+) -> Result<(DecodedMesh, DecodeTimings), DecodeError> {
+    let mut timings = DecodeTimings::default();
+    let (buf, stats) = decode_mesh_native(data, config, &mut timings).await?;
+    let mesh = DecodedMesh::new(buf, config.clone()).with_stats(stats);
+    Ok((mesh, timings))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn decode_mesh_with_timings(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+) -> Result<(DecodedMesh, DecodeTimings), DecodeError> {
+    let mut timings = DecodeTimings::default();
+    let buf = decode_mesh_wasm_worker(data, config, &mut timings).await?;
+    let stats = MeshStats::compute(&buf, config).ok_or(DecodeError::InvalidBitstream)?;
+    let mesh = DecodedMesh::new(buf, config.clone()).with_stats(stats);
+    Ok((mesh, timings))
+}
+
+/// Like [`decode_mesh_with_stats`], writing into the first
+/// [`estimate_buffer_size`](DracoDecodeConfig::estimate_buffer_size) bytes of `out`, e.g. a
+/// mapped GPU buffer or a memory-mapped file, instead of allocating. Padding within
+/// interleaved vertices is left as it was. Point clouds decode too if `config` has no
+/// indices.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_mesh_into(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    out: &mut [u8],
+) -> Result<MeshStats, DecodeError> {
+    decode_mesh_into_native(data, config, out).await
+}
+
+/// Decodes a mesh without a config, taking the layout and attribute semantics from the
+/// bitstream. Not available on wasm, where the worker can't report the layout.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_mesh_auto(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<DecodedMesh, DecodeError> {
+    decode_mesh_auto_native(data, limits)
+}
+
+/// Decodes only the faces and positions of a mesh, e.g. for collision or navmesh
+/// generation. Vertices are the unique positions, so the result is connected across the
+/// seams of other attributes; see [`ConnectivityMesh::corner_table`] for adjacency. Draco
+/// still decodes the other attributes, but doesn't dequantize them.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_connectivity(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<ConnectivityMesh, DecodeError> {
+    decode_connectivity_native(data, limits)
+}
+
+/// Decodes every attribute of a point cloud (or mesh) like [`decode_mesh_auto`]; point
+/// clouds have no indices. Generic attributes keep all their components, e.g. 16 for
+/// spherical harmonics.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn decode_point_cloud_auto(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<DecodedMesh, DecodeError> {
+    decode_geometry_auto_native(data, limits).map(|(mesh, _)| mesh)
+}
+
+/// Decodes the POSITION attribute of a Draco point cloud as tightly packed `[f32; 3]`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn decode_point_cloud(data: &[u8], limits: &DecodeLimits) -> Option<Vec<u8>> {
+    let out = decode_point_cloud_native(data, limits);
+    (!out.is_empty()).then_some(out)
 }
 
 #[cfg(test)]
@@ -25,10 +179,28 @@ mod tests {
 
     #[cfg(not(target_arch = "wasm32"))]
     use super::ffi::{debug_estimate_draco_buffer_len, decode_point_cloud_native};
-    use super::utils::{AttributeDataType, DracoDecodeConfig};
+    use super::header::{EncoderMethod, GeometryType, encoded_counts, inspect_header};
+    use super::layout::{IndexFormat, VertexLayout};
+    use super::utils::{AttributeDataType, DecodeLimits, DracoDecodeConfig};
+    #[cfg(not(target_arch = "wasm32"))]
+    use super::validation::{ConfigMismatch, ValidationMode};
+    use crate::CornerTable;
     use crate::decode_mesh;
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::{
+        AttributeSemantic, AttributeValues, DecodeError, DecodedMesh, DracoDecoder, GeometryError,
+        MeshStats, NormalWeighting, OutputMode,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::{
+        DracoStreamDecoder, StreamError, StreamStatus, decode_connectivity, decode_mesh_auto,
+        decode_mesh_from_path, decode_mesh_from_reader, decode_mesh_into, decode_mesh_with_stats,
+        decode_mesh_with_timings, decode_point_cloud_auto, try_decode_mesh,
+    };
     use std::collections::HashSet;
     use std::fs::{self};
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::time::Duration;
 
     fn quantize(v: &[f32]) -> [i32; 3] {
         [
@@ -38,11 +210,22 @@ mod tests {
         ]
     }
 
+    // The layout of assets/20/20_data.bin.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn model_config() -> DracoDecodeConfig {
+        let mut config = DracoDecodeConfig::new(3254, 4368);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(1, AttributeDataType::Float32);
+        config
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     #[test]
+    #[allow(clippy::manual_is_multiple_of)]
     fn test_decode_point_cloud() {
         let input = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
-        let output = decode_point_cloud_native(&input);
+        let output = decode_point_cloud_native(&input, &DecodeLimits::default());
 
         assert!(
             output.len() % 12 == 0,
@@ -67,6 +250,61 @@ mod tests {
         );
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_decode_point_cloud_wide_attribute() {
+        let input = fs::read("assets/pointcloud_16.drc").expect("Failed to read pointcloud_16.drc");
+        let cloud = decode_point_cloud_auto(&input, &DecodeLimits::default())
+            .expect("Point cloud decode failed");
+        assert_eq!(cloud.vertex_count(), 4);
+        assert_eq!(cloud.config().index_count(), 0);
+
+        let attr = cloud.config().attributes()[1];
+        assert_eq!(attr.semantic(), Some(AttributeSemantic::Generic));
+        assert_eq!(attr.dim(), 16);
+        let coefficients = cloud.attribute_as::<[f32; 16]>(1).unwrap();
+        for (i, values) in coefficients.iter().enumerate() {
+            let expected: Vec<f32> = (0..16).map(|j| (i * 16 + j) as f32).collect();
+            assert_eq!(values.as_slice(), expected.as_slice());
+        }
+        let stats = &cloud.stats().unwrap().attributes[1];
+        assert_eq!(stats.min[15], 15.0);
+        assert_eq!(stats.max[15], 63.0);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_decode_64bit_attribute() {
+        let input =
+            fs::read("assets/pointcloud_f64.drc").expect("Failed to read pointcloud_f64.drc");
+        let mut cloud = decode_point_cloud_auto(&input, &DecodeLimits::default())
+            .expect("Point cloud decode failed");
+        let attr = cloud.config().attributes()[0];
+        assert_eq!(attr.data_type(), AttributeDataType::Float64);
+        assert_eq!(cloud.data().len(), cloud.config().estimate_buffer_size());
+        assert_eq!(cloud.data().len(), 3 * 3 * 8);
+
+        let positions = AttributeValues::from_bytes(attr.data_type(), cloud.data());
+        let AttributeValues::Float64(positions) = positions else {
+            panic!("Expected Float64 values, got {positions:?}");
+        };
+        assert_eq!(positions[..3], [6378137.125, 0.5, -12.25]);
+        let stats = &cloud.stats().unwrap().attributes[0];
+        assert_eq!(stats.min, [6378137.125, 0.5, -14.125]);
+        assert_eq!(stats.max, [6378139.5, 2.75, -12.25]);
+
+        assert_eq!(cloud.convert_64bit_to_f32(), 1);
+        assert_eq!(
+            cloud.config().attributes()[0].data_type(),
+            AttributeDataType::Float32
+        );
+        assert_eq!(cloud.data().len(), 3 * 3 * 4);
+        assert_eq!(
+            cloud.positions::<[f32; 3]>().unwrap()[2],
+            [6378139.5, 2.75, -14.125]
+        );
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     #[test]
     fn test_mesh_buffer_len() {
@@ -86,6 +324,822 @@ mod tests {
         assert_eq!(actual_len, expect_len);
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_inspect_header() {
+        let mesh = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let header = inspect_header(&mesh).expect("Failed to parse mesh header");
+        assert_eq!(header.version(), (2, 2));
+        assert_eq!(header.geometry_type(), GeometryType::TriangularMesh);
+        assert_eq!(header.encoder_method(), EncoderMethod::Edgebreaker);
+        assert!(!header.has_metadata());
+
+        let point_cloud = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
+        let header = inspect_header(&point_cloud).expect("Failed to parse point cloud header");
+        assert_eq!(header.geometry_type(), GeometryType::PointCloud);
+        assert_eq!(header.encoder_method(), EncoderMethod::KdTree);
+
+        assert!(inspect_header(b"DRACO").is_none());
+        assert!(inspect_header(&point_cloud[1..]).is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_encoded_counts() {
+        // Edgebreaker declares the encoded vertices, before seams split them into 3254 points.
+        let mesh = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let counts = encoded_counts(&mesh).expect("Failed to read mesh counts");
+        assert_eq!((counts.points, counts.faces), (930, 1456));
+
+        let point_cloud = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
+        let counts = encoded_counts(&point_cloud).expect("Failed to read point cloud counts");
+        assert_eq!((counts.points, counts.faces), (3, 0));
+
+        // A sequential mesh with metadata on attribute 0 and a nested geometry metadata.
+        let mut metadata = b"DRACO\x02\x02\x01\x00\x00\x80".to_vec();
+        metadata.extend([1, 0, 1, 1, b'a', 1, 7, 0, 0, 1, 1, b'c', 0, 0, 5, 9]);
+        let counts = encoded_counts(&metadata).expect("Failed to skip metadata");
+        assert_eq!((counts.points, counts.faces), (9, 5));
+        assert!(encoded_counts(&metadata[..metadata.len() - 1]).is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_limits() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+
+        let mut config = model_config();
+
+        config.set_limits(DecodeLimits::new().with_max_faces(1455));
+        assert!(decode_mesh(&input, &config).await.is_none());
+
+        config.set_limits(DecodeLimits::new().with_max_points(3253));
+        assert!(decode_mesh(&input, &config).await.is_none());
+
+        config.set_limits(DecodeLimits::new().with_max_output_bytes(1024));
+        assert!(decode_mesh(&input, &config).await.is_none());
+
+        config.set_limits(
+            DecodeLimits::new()
+                .with_max_faces(1456)
+                .with_max_points(3254)
+                .with_max_output_bytes(config.estimate_buffer_size()),
+        );
+        assert!(decode_mesh(&input, &config).await.is_some());
+
+        let point_cloud = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
+        assert!(
+            decode_point_cloud_native(&point_cloud, &DecodeLimits::new().with_max_points(2))
+                .is_empty()
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_stream_decode() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+
+        let config = model_config();
+        let expected = decode_mesh(&input, &config).await.expect("Decode failed");
+
+        let mut decoder = DracoStreamDecoder::new(config.clone()).with_expected_len(input.len());
+        decoder.feed(&input[..5]).unwrap();
+        assert_eq!(decoder.poll(), StreamStatus::NeedHeader);
+        decoder.feed(&input[5..100]).unwrap();
+        let StreamStatus::Receiving { header, received } = decoder.poll() else {
+            panic!("Expected the header to be parsed, got {:?}", decoder.poll());
+        };
+        assert_eq!(header.geometry_type(), GeometryType::TriangularMesh);
+        assert_eq!(received, 100);
+        assert_eq!(decoder.layout().byte_length, expected.len());
+        for chunk in input[100..].chunks(1000) {
+            decoder.feed(chunk).unwrap();
+        }
+        assert!(matches!(decoder.poll(), StreamStatus::Complete { .. }));
+        assert_eq!(decoder.finish().await.unwrap().data(), expected.as_slice());
+
+        let mesh = decode_mesh_from_reader(input.as_slice(), &config)
+            .await
+            .expect("Reader decode failed");
+        assert_eq!(mesh.data(), expected.as_slice());
+
+        let point_cloud = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
+        let mut decoder = DracoStreamDecoder::new(config.clone());
+        assert_eq!(
+            decoder.feed(&point_cloud[..16]),
+            Err(DecodeError::UnexpectedGeometryType)
+        );
+        let mut decoder = DracoStreamDecoder::new(config);
+        decoder.feed(&input[..input.len() / 2]).unwrap();
+        assert_eq!(
+            decoder.finish().await.unwrap_err(),
+            DecodeError::InvalidBitstream
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_from_path() {
+        let config = model_config();
+
+        let mesh = decode_mesh_from_path("assets/20/20_data.bin", &config)
+            .await
+            .expect("Path decode failed");
+        assert_eq!(mesh.data().len(), config.estimate_buffer_size());
+        assert!(matches!(
+            decode_mesh_from_path("assets/missing.drc", &config).await,
+            Err(StreamError::Io(_))
+        ));
+
+        #[cfg(feature = "tokio")]
+        {
+            let file = tokio::fs::File::open("assets/20/20_data.bin")
+                .await
+                .unwrap();
+            let streamed = crate::decode_mesh_from_async_read(file, &config)
+                .await
+                .expect("Async read decode failed");
+            assert_eq!(streamed.data(), mesh.data());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_into_mapped_file() {
+        use crate::mmap::{MappedMesh, decode_mesh_to_file};
+
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let config = model_config();
+        let expected = decode_mesh(&input, &config).await.expect("Decode failed");
+
+        let mut out = vec![0; expected.len() + 4];
+        decode_mesh_into(&input, &config, &mut out).await.unwrap();
+        assert_eq!(&out[..expected.len()], expected.as_slice());
+        assert_eq!(
+            decode_mesh_into(&input, &config, &mut out[..100])
+                .await
+                .unwrap_err(),
+            DecodeError::OutputTooSmall {
+                needed: expected.len(),
+                found: 100
+            }
+        );
+
+        let mut config = DracoDecodeConfig::new(4, 0);
+        config.add_semantic_attribute(AttributeSemantic::Position, 3, AttributeDataType::Float32);
+        config.add_semantic_attribute(AttributeSemantic::Generic, 16, AttributeDataType::Float32);
+        let path = std::env::temp_dir().join("draco_decoder_mapped_point_cloud.bin");
+        decode_mesh_to_file("assets/pointcloud_16.drc", &path, &config)
+            .await
+            .expect("Mapped decode failed");
+
+        let mapped = MappedMesh::open(&path).expect("Failed to open mapped mesh");
+        assert_eq!(mapped.config().vertex_count(), 4);
+        assert_eq!(
+            mapped.config().attributes()[1].semantic(),
+            Some(AttributeSemantic::Generic)
+        );
+        let cloud = mapped.to_decoded_mesh();
+        assert_eq!(cloud.attribute_as::<[f32; 16]>(1).unwrap()[3][15], 63.0);
+        drop(mapped);
+
+        fs::write(&path, b"DRCOMESH").unwrap();
+        assert!(MappedMesh::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_reusable_decoder() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let config = model_config();
+        let expected = decode_mesh(&input, &config).await.expect("Decode failed");
+
+        let mut decoder = DracoDecoder::new(config.clone()).with_index_format(IndexFormat::U32);
+        let mesh = decoder.decode(&input).expect("Decoder failed");
+        assert_eq!(mesh.index_format(), IndexFormat::U32);
+        let wide = mesh.layout().repack(&config.layout(), &expected);
+        assert_eq!(mesh.data(), wide.as_slice());
+
+        decoder.set_config(config);
+        assert_eq!(
+            decoder.decode_to_scratch(&input).unwrap(),
+            expected.as_slice()
+        );
+        assert!(decoder.decode_to_scratch(&input[..100]).is_err());
+
+        let stats = decoder.stats();
+        assert!(stats.timings.attributes > Duration::ZERO);
+        assert_eq!(stats.decodes, 2);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.bytes_in, 2 * input.len() as u64);
+        assert_eq!(stats.bytes_out, (wide.len() + expected.len()) as u64);
+
+        assert_eq!(
+            DracoDecoder::new(model_config())
+                .skip_attribute_transform(AttributeSemantic::Joints(0))
+                .unwrap_err(),
+            DecodeError::UnsupportedSemantic(AttributeSemantic::Joints(0))
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_timings() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let config = model_config();
+
+        let (mesh, timings) = decode_mesh_with_timings(&input, &config)
+            .await
+            .expect("Decode failed");
+        assert_eq!(mesh.data().len(), config.estimate_buffer_size());
+        assert!(timings.connectivity > Duration::ZERO);
+        assert!(timings.attributes > Duration::ZERO);
+        assert!(timings.write > Duration::ZERO);
+        assert_eq!(timings.worker, Duration::ZERO);
+        assert_eq!(
+            timings.total(),
+            timings.header
+                + timings.connectivity
+                + timings.attributes
+                + timings.validate
+                + timings.write
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_config_validation() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+
+        let mut config = DracoDecodeConfig::new(3254, 4368);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(2, AttributeDataType::Float32);
+        config.add_attribute(1, AttributeDataType::Float32);
+
+        let Err(DecodeError::ConfigMismatch(mismatches)) = try_decode_mesh(&input, &config).await
+        else {
+            panic!("Expected a config mismatch")
+        };
+        assert_eq!(
+            mismatches,
+            vec![ConfigMismatch::Attribute {
+                index: 1,
+                expected_dim: 2,
+                expected_type: AttributeDataType::Float32,
+                found_dim: 3,
+                found_type: Some(AttributeDataType::Float32),
+            }]
+        );
+        assert_eq!(
+            mismatches[0].to_string(),
+            "attribute 1: expected dim 2 Float32, found dim 3 Float32"
+        );
+
+        // lenient mode converts the float batch id, strict mode rejects it
+        let mut config = DracoDecodeConfig::new(3254, 4368);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(1, AttributeDataType::UInt16);
+        let buf = try_decode_mesh(&input, &config)
+            .await
+            .expect("Lenient decode with converted attribute failed");
+        assert_eq!(buf.len(), config.estimate_buffer_size());
+
+        config.set_validation(ValidationMode::Strict);
+        assert!(matches!(
+            try_decode_mesh(&input, &config).await,
+            Err(DecodeError::ConfigMismatch(_))
+        ));
+    }
+
+    #[cfg(all(feature = "gltf", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn test_gltf_mesh() {
+        use base64::Engine;
+
+        use crate::gltf::GltfDocument;
+
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&fs::read("assets/20/20.gltf").expect("Failed to read glTF"))
+                .unwrap();
+        // Target 0 moves vertex 5 through a sparse accessor, target 1 has no data.
+        let sparse: Vec<u8> = [5u16.to_le_bytes().as_slice(), &[0, 0]]
+            .concat()
+            .into_iter()
+            .chain([1.0f32, 2.0, 3.0].iter().flat_map(|v| v.to_le_bytes()))
+            .collect();
+        let uri = base64::engine::general_purpose::STANDARD.encode(&sparse);
+        let push = |json: &mut serde_json::Value, key: &str, value: serde_json::Value| {
+            json[key].as_array_mut().unwrap().push(value);
+        };
+        push(
+            &mut json,
+            "buffers",
+            serde_json::json!({
+                "uri": format!("data:application/octet-stream;base64,{uri}"),
+                "byteLength": sparse.len(),
+            }),
+        );
+        push(
+            &mut json,
+            "bufferViews",
+            serde_json::json!({ "buffer": 1, "byteOffset": 0, "byteLength": 4 }),
+        );
+        push(
+            &mut json,
+            "bufferViews",
+            serde_json::json!({ "buffer": 1, "byteOffset": 4, "byteLength": 12 }),
+        );
+        push(
+            &mut json,
+            "accessors",
+            serde_json::json!({
+                "componentType": 5126, "count": 3254, "type": "VEC3",
+                "sparse": {
+                    "count": 1,
+                    "indices": { "bufferView": 1, "componentType": 5123 },
+                    "values": { "bufferView": 2 },
+                },
+            }),
+        );
+        push(
+            &mut json,
+            "accessors",
+            serde_json::json!({ "componentType": 5126, "count": 3254, "type": "VEC3" }),
+        );
+        let mut primitive = json["meshes"][0]["primitives"][0].clone();
+        primitive["targets"] = serde_json::json!([{ "POSITION": 4 }, { "NORMAL": 5 }]);
+        json["meshes"][0]["primitives"] = serde_json::json!([primitive.clone(), primitive]);
+        json["meshes"][0]["weights"] = serde_json::json!([0.5, 0.0]);
+
+        let mut document = GltfDocument::from_slice(&serde_json::to_vec(&json).unwrap())
+            .expect("Failed to parse glTF");
+        assert_eq!(document.missing_buffers(), [(0, "20_data.bin")]);
+        document.set_buffer(
+            0,
+            fs::read("assets/20/20_data.bin").expect("Failed to read buffer"),
+        );
+
+        let mesh = document
+            .decode_mesh(0, &DecodeLimits::default())
+            .await
+            .expect("glTF mesh decode failed");
+        assert_eq!(mesh.weights, [0.5, 0.0]);
+        assert_eq!(mesh.primitives.len(), 2);
+        for primitive in &mesh.primitives {
+            assert_eq!(primitive.mesh.vertex_count(), 3254);
+            assert_eq!(primitive.mesh.config().index_count(), 4368);
+            assert_eq!(primitive.material, Some(0));
+            assert!(primitive.mesh.positions::<[f32; 3]>().is_some());
+            assert!(primitive.mesh.stats().unwrap().bounds.is_some());
+
+            let moved = primitive.targets[0].positions().unwrap();
+            assert_eq!(moved[5], [1.0, 2.0, 3.0]);
+            assert_eq!(moved.iter().filter(|delta| **delta != [0.0; 3]).count(), 1);
+            assert!(primitive.targets[1].positions().is_none());
+            assert!(
+                primitive.targets[1]
+                    .normals()
+                    .unwrap()
+                    .iter()
+                    .all(|n| *n == [0.0; 3])
+            );
+        }
+    }
+
+    #[cfg(all(feature = "gltf", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn test_gltf_attribute_subset() {
+        use crate::gltf::GltfDocument;
+
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&fs::read("assets/20/20.gltf").expect("Failed to read glTF"))
+                .unwrap();
+        // A second primitive on the same bufferView that only references POSITION, which is
+        // unique id 1 after NORMAL.
+        let mut primitive = json["meshes"][0]["primitives"][0].clone();
+        primitive["attributes"] = serde_json::json!({ "POSITION": 2 });
+        primitive["extensions"]["KHR_draco_mesh_compression"]["attributes"] =
+            serde_json::json!({ "POSITION": 1 });
+        json["meshes"][0]["primitives"]
+            .as_array_mut()
+            .unwrap()
+            .push(primitive);
+
+        let mut document = GltfDocument::from_slice(&serde_json::to_vec(&json).unwrap())
+            .expect("Failed to parse glTF");
+        document.set_buffer(
+            0,
+            fs::read("assets/20/20_data.bin").expect("Failed to read buffer"),
+        );
+        let mesh = document
+            .decode_mesh(0, &DecodeLimits::default())
+            .await
+            .expect("glTF mesh decode failed");
+
+        let [full, subset] = &mesh.primitives[..] else {
+            panic!("expected two primitives");
+        };
+        assert_eq!(subset.mesh.config().attribute_slice().len(), 1);
+        assert!(subset.mesh.normals::<[f32; 3]>().is_none());
+        assert_eq!(
+            subset.mesh.positions::<[f32; 3]>(),
+            full.mesh.positions::<[f32; 3]>()
+        );
+    }
+
+    #[cfg(all(feature = "gltf", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn test_gltf_compressed_targets() {
+        use crate::gltf::{GltfDocument, GltfError};
+
+        let gltf = fs::read("assets/20/20.gltf").expect("Failed to read glTF");
+        let buffer = fs::read("assets/20/20_data.bin").expect("Failed to read buffer");
+        let mut json: serde_json::Value = serde_json::from_slice(&gltf).unwrap();
+        // NORMAL (unique id 0) becomes the deltas of a compressed morph target, whose
+        // accessor has no bufferView.
+        let primitive = &mut json["meshes"][0]["primitives"][0];
+        primitive["attributes"] = serde_json::json!({ "POSITION": 2, "_BATCHID": 3 });
+        primitive["targets"] = serde_json::json!([{ "NORMAL": 4 }]);
+        let extension = &mut primitive["extensions"]["KHR_draco_mesh_compression"];
+        extension["attributes"] = serde_json::json!({ "POSITION": 1, "_BATCHID": 2 });
+        extension["targets"] = serde_json::json!([{ "NORMAL": 0 }]);
+        // A second mesh with an uncompressed POSITION of 2^31 zeros.
+        let accessors = json["accessors"].as_array_mut().unwrap();
+        accessors.push(serde_json::json!({ "componentType": 5126, "count": 3254, "type": "VEC3" }));
+        accessors.push(
+            serde_json::json!({ "componentType": 5126, "count": 1u64 << 31, "type": "VEC3" }),
+        );
+        json["meshes"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({ "primitives": [{ "attributes": { "POSITION": 5 } }] }));
+
+        let mut document = GltfDocument::from_slice(&serde_json::to_vec(&json).unwrap())
+            .expect("Failed to parse glTF");
+        document.set_buffer(0, buffer.clone());
+        let limits = DecodeLimits::new().with_max_output_bytes(1 << 20);
+        let mesh = document
+            .decode_mesh(0, &limits)
+            .await
+            .expect("glTF mesh decode failed");
+        let primitive = &mesh.primitives[0];
+        assert!(primitive.mesh.normals::<[f32; 3]>().is_none());
+
+        let mut original = GltfDocument::from_slice(&gltf).expect("Failed to parse glTF");
+        original.set_buffer(0, buffer);
+        let original = original
+            .decode_mesh(0, &limits)
+            .await
+            .expect("glTF mesh decode failed");
+        let original = &original.primitives[0].mesh;
+        assert_eq!(
+            primitive.mesh.positions::<[f32; 3]>(),
+            original.positions::<[f32; 3]>()
+        );
+        assert_eq!(
+            primitive.targets[0].normals().unwrap(),
+            &original.normals::<[f32; 3]>().unwrap()[..]
+        );
+
+        assert!(matches!(
+            document.decode_mesh(1, &limits).await,
+            Err(GltfError::Decode(DecodeError::LimitExceeded))
+        ));
+    }
+
+    #[cfg(all(feature = "gltf", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn test_gltf_wide_indices() {
+        use base64::Engine;
+
+        use crate::gltf::{GltfDocument, GltfError};
+
+        // Three indices into 70000 zeroed positions, the last one past u16.
+        let document = |last: u32| {
+            let indices: Vec<u8> = [0u32, 1, last]
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect();
+            let uri = base64::engine::general_purpose::STANDARD.encode(&indices);
+            let json = serde_json::json!({
+                "buffers": [{
+                    "uri": format!("data:application/octet-stream;base64,{uri}"),
+                    "byteLength": 12,
+                }],
+                "bufferViews": [{ "buffer": 0, "byteLength": 12 }],
+                "accessors": [
+                    { "bufferView": 0, "componentType": 5125, "count": 3, "type": "SCALAR" },
+                    { "componentType": 5126, "count": 70000, "type": "VEC3" },
+                ],
+                "meshes": [{ "primitives": [{ "attributes": { "POSITION": 1 }, "indices": 0 }] }],
+            });
+            GltfDocument::from_slice(&serde_json::to_vec(&json).unwrap())
+                .expect("Failed to parse glTF")
+        };
+
+        let mesh = document(69999)
+            .decode_mesh(0, &DecodeLimits::default())
+            .await
+            .expect("glTF mesh decode failed");
+        let mesh = &mesh.primitives[0].mesh;
+        assert_eq!(mesh.index_format(), IndexFormat::U32);
+        assert_eq!(mesh.indices(), vec![0, 1, 69999]);
+
+        assert!(matches!(
+            document(70000)
+                .decode_mesh(0, &DecodeLimits::default())
+                .await,
+            Err(GltfError::Invalid(_))
+        ));
+    }
+
+    #[cfg(all(feature = "tiles3d", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_tiles3d() {
+        use crate::tiles3d::{decode_pnts, parse_b3dm};
+
+        fn tile(magic: &[u8], feature_table: &str, feature_binary: &[u8], body: &[u8]) -> Vec<u8> {
+            let mut json = feature_table.as_bytes().to_vec();
+            json.resize(json.len().next_multiple_of(8), b' ');
+            let lengths = [json.len(), feature_binary.len(), 0, 0];
+            let byte_length = 28 + json.len() + feature_binary.len() + body.len();
+            let mut out = magic.to_vec();
+            for word in [1, byte_length].into_iter().chain(lengths) {
+                out.extend_from_slice(&(word as u32).to_le_bytes());
+            }
+            out.extend_from_slice(&json);
+            out.extend_from_slice(feature_binary);
+            out.extend_from_slice(body);
+            out
+        }
+
+        let draco = fs::read("assets/pointcloud.drc").expect("Failed to read pointcloud.drc");
+        let feature_table = format!(
+            r#"{{"POINTS_LENGTH":3,"RTC_CENTER":[1,2,3],"extensions":{{"3DTILES_draco_point_compression":{{"properties":{{"POSITION":0}},"byteOffset":0,"byteLength":{}}}}}}}"#,
+            draco.len()
+        );
+        let pnts = decode_pnts(
+            &tile(b"pnts", &feature_table, &draco, &[]),
+            &DecodeLimits::default(),
+        )
+        .expect("Failed to decode pnts");
+        assert_eq!(pnts.points_length, 3);
+        assert_eq!(pnts.rtc_center, Some([1.0, 2.0, 3.0]));
+        let actual: HashSet<[i32; 3]> = pnts.positions.iter().map(|p| quantize(p)).collect();
+        assert!(actual.contains(&quantize(&[2.0, 2.0, 2.0])));
+        assert!(pnts.colors.is_none());
+
+        // RGB isn't compressed, so it stays in the feature table after the Draco payload.
+        let feature_table = format!(
+            r#"{{"POINTS_LENGTH":3,"RGB":{{"byteOffset":{0}}},"extensions":{{"3DTILES_draco_point_compression":{{"properties":{{"POSITION":0}},"byteOffset":0,"byteLength":{0}}}}}}}"#,
+            draco.len()
+        );
+        let binary = [draco.as_slice(), &[1, 2, 3, 4, 5, 6, 7, 8, 9]].concat();
+        let pnts = decode_pnts(
+            &tile(b"pnts", &feature_table, &binary, &[]),
+            &DecodeLimits::default(),
+        )
+        .expect("Failed to decode pnts");
+        assert_eq!(
+            pnts.colors,
+            Some(vec![[1, 2, 3, 255], [4, 5, 6, 255], [7, 8, 9, 255]])
+        );
+
+        let b3dm = tile(b"b3dm", "{\"BATCH_LENGTH\":0}", &[], b"glTF");
+        let b3dm = parse_b3dm(&b3dm).expect("Failed to parse b3dm");
+        assert_eq!(b3dm.glb, b"glTF");
+        assert_eq!(b3dm.tables.feature_table["BATCH_LENGTH"], 0);
+        assert!(parse_b3dm(b"b3dm").is_err());
+    }
+
+    #[test]
+    fn test_corner_table() {
+        let quad = CornerTable::new(&[0, 1, 2, 0, 2, 3], 4).unwrap();
+        assert_eq!(quad.opposite(1), Some(5));
+        assert_eq!(quad.vertex_ring(0), [3, 2, 1]);
+        assert_eq!(quad.boundary_edges().len(), 4);
+        assert!(quad.is_manifold() && !quad.is_closed());
+        assert!(quad.is_boundary_vertex(0));
+
+        let tetrahedron = CornerTable::new(&[0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2], 4).unwrap();
+        assert!(tetrahedron.is_closed());
+        assert_eq!(tetrahedron.vertex_ring(0).len(), 3);
+
+        let fin = CornerTable::new(&[0, 1, 2, 1, 0, 3, 0, 1, 4], 5).unwrap();
+        assert_eq!(fin.non_manifold_edges(), [[0, 1]]);
+        assert!(!fin.is_manifold());
+
+        assert_eq!(
+            CornerTable::new(&[0, 1, 4], 4).unwrap_err(),
+            GeometryError::InvalidIndex
+        );
+        // Corner 0 claims corner 3 across an edge the second face doesn't have.
+        let unpaired = [3, u32::MAX, u32::MAX, 0, u32::MAX, u32::MAX];
+        assert!(CornerTable::with_opposites(&[0, 1, 2, 0, 1, 3], &unpaired, 4).is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_connectivity() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let mesh = decode_connectivity(&input, &DecodeLimits::default())
+            .await
+            .expect("Connectivity decode failed");
+        assert_eq!(mesh.indices.len(), 4368);
+        let table = mesh.corner_table().expect("Invalid corner table");
+        assert_eq!(table.num_faces(), 1456);
+        assert_eq!(table.num_vertices(), mesh.positions.len());
+        assert!(table.is_manifold());
+
+        // A single edgebreaker triangle.
+        let input = fs::read("assets/mesh.drc").expect("Failed to read mesh.drc");
+        let mesh = decode_connectivity(&input, &DecodeLimits::default())
+            .await
+            .expect("Connectivity decode failed");
+        let table = mesh.corner_table().expect("Invalid corner table");
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(table.num_faces(), 1);
+        assert_eq!(table.boundary_edges().len(), 3);
+        assert!(table.is_manifold() && !table.is_closed());
+        assert_eq!(table.vertex_ring(mesh.indices[0]).len(), 2);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_generate_normals() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let decoded = decode_mesh_auto(&input, &DecodeLimits::default())
+            .await
+            .expect("Auto decode failed");
+
+        let (data, config) = decoded.into_parts();
+        let mut mesh = DecodedMesh::new(data.clone(), config.clone());
+        let index = mesh
+            .compute_smooth_normals(NormalWeighting::Angle)
+            .expect("Smooth normals failed");
+        assert_eq!(index, 3);
+        assert_eq!(mesh.data().len(), mesh.config().estimate_buffer_size());
+        let normals = mesh.attribute_as::<[f32; 3]>(3).unwrap();
+        let decoded_normals = mesh.normals::<[f32; 3]>().unwrap();
+        let similar = normals
+            .iter()
+            .zip(decoded_normals.iter())
+            .filter(|(a, b)| a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f32>() > 0.5)
+            .count();
+        assert!(similar > normals.len() * 9 / 10);
+
+        let mut mesh = DecodedMesh::new(data, config);
+        mesh.compute_flat_normals().expect("Flat normals failed");
+        assert_eq!(mesh.vertex_count(), 4368);
+        assert_eq!(mesh.indices()[..4], [0, 1, 2, 3]);
+        assert_eq!(mesh.data().len(), mesh.config().estimate_buffer_size());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_normalize_skinning() {
+        let mut config = DracoDecodeConfig::new(2, 0);
+        config.add_gltf_attribute("POSITION", 3, AttributeDataType::Float32, false);
+        config.add_gltf_attribute("JOINTS_0", 4, AttributeDataType::UInt8, false);
+        config.add_gltf_attribute("WEIGHTS_0", 4, AttributeDataType::UInt8, true);
+        let positions = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut data = bytemuck::cast_slice(&positions).to_vec();
+        data.extend_from_slice(&[0, 1, 2, 3, 4, 5, 6, 7]);
+        data.extend_from_slice(&[128, 128, 0, 0, 85, 85, 85, 0]);
+        let mut mesh = DecodedMesh::new(data, config.clone());
+
+        mesh.normalize_skinning()
+            .expect("Skinning normalization failed");
+        let attributes = mesh.config().attributes();
+        assert_eq!(attributes[1].data_type(), AttributeDataType::UInt16);
+        assert_eq!(attributes[2].data_type(), AttributeDataType::Float32);
+        assert_eq!(mesh.data().len(), mesh.config().estimate_buffer_size());
+        assert_eq!(mesh.positions::<[f32; 3]>().unwrap()[1], [4.0, 5.0, 6.0]);
+        let joints: Vec<u16> = bytemuck::pod_collect_to_vec(mesh.attribute_bytes(1).unwrap());
+        assert_eq!(joints, [0, 1, 2, 3, 4, 5, 6, 7]);
+        let weights = mesh.attribute_as::<[f32; 4]>(2).unwrap();
+        assert_eq!(weights[0], [0.5, 0.5, 0.0, 0.0]);
+        assert!((weights[1].iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        let mut config = DracoDecodeConfig::new(1, 0);
+        config.add_gltf_attribute("WEIGHTS_0", 3, AttributeDataType::Float32, false);
+        let mut mesh = DecodedMesh::new(vec![0; 12], config);
+        assert_eq!(
+            mesh.normalize_skinning(),
+            Err(GeometryError::UnsupportedDim {
+                semantic: AttributeSemantic::Weights(0),
+                dim: 3,
+            })
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_per_corner_output() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+        let mut config = DracoDecodeConfig::new(4368, 4368);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(1, AttributeDataType::Float32);
+        config.set_output_mode(OutputMode::PerCorner);
+
+        let mut mesh = decode_mesh_with_stats(&input, &config)
+            .await
+            .expect("Per-corner decode failed");
+        assert_eq!(mesh.data().len(), config.estimate_buffer_size());
+        assert!(
+            mesh.indices()
+                .iter()
+                .enumerate()
+                .all(|(i, &index)| i as u32 == index)
+        );
+
+        let removed = mesh.weld().expect("Weld failed");
+        assert_eq!(mesh.vertex_count() as usize, 4368 - removed);
+        assert!(mesh.vertex_count() <= 3254);
+        assert_eq!(mesh.config().index_count(), 4368);
+        assert_eq!(mesh.data().len(), mesh.config().estimate_buffer_size());
+        assert!(mesh.stats().is_some());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_decode_mesh_auto() {
+        let input = fs::read("assets/20/20_data.bin").expect("Failed to read model file");
+
+        let mesh = decode_mesh_auto(&input, &DecodeLimits::default())
+            .await
+            .expect("Auto decode failed");
+        let semantics: Vec<_> = mesh
+            .config()
+            .attributes()
+            .iter()
+            .map(|attr| attr.semantic())
+            .collect();
+        assert_eq!(
+            semantics,
+            [
+                Some(AttributeSemantic::Normal),
+                Some(AttributeSemantic::Position),
+                Some(AttributeSemantic::Generic),
+            ]
+        );
+        assert_eq!(mesh.vertex_count(), 3254);
+        assert_eq!(mesh.data().len(), mesh.config().estimate_buffer_size());
+        let (position, bytes) = mesh.attribute(AttributeSemantic::Position).unwrap();
+        assert_eq!(bytes.len(), position.length() as usize);
+        let positions = mesh.positions::<[f32; 3]>().unwrap();
+        assert!(matches!(positions, std::borrow::Cow::Borrowed(_)));
+        assert_eq!(positions.len(), 3254);
+        assert!(mesh.uvs::<[f32; 2]>().is_none());
+        #[cfg(feature = "glam")]
+        assert_eq!(mesh.normals::<glam::Vec3>().unwrap().len(), 3254);
+
+        // The accessors of assets/20/20.gltf.
+        let stats = mesh.stats().expect("Missing stats");
+        assert_eq!(stats.attributes[2].min, [0.0]);
+        assert_eq!(stats.attributes[2].max, [3.0]);
+        let bounds = stats.bounds.expect("Missing bounds");
+        for (found, expected) in bounds
+            .aabb
+            .min
+            .iter()
+            .zip([-421.37863, -253.087, -93.64516])
+        {
+            assert!((found - expected).abs() < 0.1);
+        }
+        for (found, expected) in bounds.aabb.max.iter().zip([350.3248, 133.63625, 183.04193]) {
+            assert!((found - expected).abs() < 0.1);
+        }
+        assert_eq!(
+            MeshStats::compute(mesh.data(), mesh.config()).as_ref(),
+            Some(stats)
+        );
+
+        assert_eq!(
+            AttributeSemantic::from_gltf("TEXCOORD_1"),
+            AttributeSemantic::TexCoord(1)
+        );
+        assert_eq!(
+            AttributeSemantic::from_gltf("_BATCHID"),
+            AttributeSemantic::Generic
+        );
+
+        #[cfg(feature = "bevy")]
+        {
+            use ::bevy::asset::RenderAssetUsages;
+            use ::bevy::mesh::Mesh;
+
+            let bevy_mesh = crate::bevy::to_bevy_mesh(&mesh, RenderAssetUsages::default())
+                .expect("Bevy mesh conversion failed");
+            assert_eq!(bevy_mesh.count_vertices(), 3254);
+            assert!(bevy_mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+            assert_eq!(bevy_mesh.indices().map(|indices| indices.len()), Some(4368));
+        }
+    }
+
     async fn test_mesh(data: &[u8]) -> Vec<u8> {
         let mut config = DracoDecodeConfig::new(3254, 4368);
         config.add_attribute(3, AttributeDataType::Float32);
@@ -97,6 +1151,18 @@ mod tests {
         };
         assert_eq!(buf.len(), config.estimate_buffer_size());
 
+        let layout = config.layout();
+        let indices: &[u16] = bytemuck::cast_slice(layout.index_bytes(&buf).unwrap());
+        assert!(
+            indices
+                .iter()
+                .all(|&i| u32::from(i) < config.vertex_count())
+        );
+        assert_eq!(
+            layout.attribute_bytes(&buf, 2).map(<[u8]>::len),
+            Some(3254 * 4)
+        );
+
         buf
     }
 
@@ -117,11 +1183,12 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(all(target_arch = "wasm32", not(wasm_node_tests)))]
     wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 
     #[cfg_attr(not(target_arch = "wasm32"), test)]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[allow(deprecated)]
     fn test_config() {
         let mut config = DracoDecodeConfig::new(16744, 54663);
         config.add_attribute(3, AttributeDataType::Float32);
@@ -144,12 +1211,82 @@ mod tests {
         assert_eq!(attr_1.lenght(), 133952);
     }
 
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_buffer_layout() {
+        let mut config = DracoDecodeConfig::new(16744, 54663);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(2, AttributeDataType::Float32);
+
+        let layout = config.layout();
+        assert_eq!(layout.indices.format, IndexFormat::U16);
+        assert_eq!(layout.indices.byte_range, 0..109326);
+        assert_eq!(layout.attributes[0].byte_range, 109326..310254);
+        assert_eq!(layout.attributes[1].stride, 8);
+        assert_eq!(layout.attributes[1].element_count, 16744);
+        assert_eq!(layout.byte_length, config.estimate_buffer_size());
+
+        config.set_vertex_layout(VertexLayout::Interleaved);
+        let interleaved = config.layout();
+        assert_eq!(interleaved.vertex_range, 109328..109328 + 16744 * 20);
+        assert_eq!(interleaved.attributes[1].byte_range.start, 109328 + 12);
+        assert_eq!(interleaved.attributes[1].stride, 20);
+        assert_eq!(interleaved.byte_length, config.estimate_buffer_size());
+
+        let planar: Vec<u8> = (0..layout.byte_length).map(|i| i as u8).collect();
+        let repacked = interleaved.repack(&layout, &planar);
+        assert_eq!(repacked[109328 + 12..109328 + 20], planar[310254..310262]);
+    }
+
+    #[cfg(feature = "wgpu")]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_wgpu_vertex_buffers() {
+        let mut config = DracoDecodeConfig::new(16744, 54663);
+        config.add_attribute(3, AttributeDataType::Float32);
+        config.add_attribute(2, AttributeDataType::Float32);
+        config.set_vertex_layout(VertexLayout::Interleaved);
+        let layout = config.layout();
+
+        let buffers = layout
+            .wgpu_vertex_buffers()
+            .expect("Interleaved layout is valid");
+        let layouts = buffers.layouts();
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].array_stride, 20);
+        assert_eq!(
+            layouts[0].attributes[1].format,
+            ::wgpu::VertexFormat::Float32x2
+        );
+        assert_eq!(layouts[0].attributes[1].offset, 12);
+        assert_eq!(layout.index_format_wgpu(), ::wgpu::IndexFormat::Uint16);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_config_overflow() {
+        let mut config = DracoDecodeConfig::new(u32::MAX, u32::MAX);
+        config.add_attribute(u32::MAX, AttributeDataType::Float64);
+        config.add_attribute(3, AttributeDataType::Float32);
+
+        assert_eq!(config.index_length(), u32::MAX);
+        assert_eq!(config.attribute_slice()[0].length(), u32::MAX);
+        assert_eq!(config.attribute_slice()[1].offset(), u32::MAX);
+        assert_eq!(config.checked_buffer_size(), None);
+        assert_eq!(config.estimate_buffer_size(), usize::MAX);
+        assert_eq!(config.layout().byte_length, usize::MAX);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        assert!(matches!(
+            DracoDecoder::new(config).decode(b"DRACO"),
+            Err(DecodeError::LimitExceeded)
+        ));
+    }
+
     #[cfg(target_arch = "wasm32")]
     #[wasm_bindgen_test]
-    fn test_decode_mesh_wasm() {
-        use super::*;
+    async fn test_decode_mesh_wasm() {
         use wasm_bindgen::*;
-        use wasm_bindgen_futures::spawn_local;
         use web_sys::console;
 
         async fn fetch_binary(url: &str) -> Result<Vec<u8>, JsValue> {
@@ -174,15 +1311,29 @@ mod tests {
             Ok(body)
         }
 
-        spawn_local(async {
-            console::log_1(&"Starting wasm test...".into());
+        // Outside the browser there is no `window`, read the asset from disk instead.
+        fn read_binary(path: &str) -> Result<Vec<u8>, JsValue> {
+            let fs = js_sys::eval("globalThis.process.getBuiltinModule('fs')")?;
+            let read_file_sync: js_sys::Function =
+                js_sys::Reflect::get(&fs, &JsValue::from_str("readFileSync"))?.dyn_into()?;
+            let file = read_file_sync.call1(&fs, &JsValue::from_str(path))?;
+            Ok(js_sys::Uint8Array::new(&file).to_vec())
+        }
+
+        console::log_1(&"Starting wasm test...".into());
 
-            match fetch_binary("assets/extracted_model/extracted_model_data.bin").await {
-                Ok(data) => {
-                    test_mesh(&data).await;
-                }
-                Err(e) => console::error_1(&format!("Fetch error: {:?}", e).into()),
+        let path = "assets/20/20_data.bin";
+        let data = if web_sys::window().is_some() {
+            fetch_binary(path).await
+        } else {
+            read_binary(path)
+        };
+
+        match data {
+            Ok(data) => {
+                test_mesh(&data).await;
             }
-        });
+            Err(e) => panic!("Fetch error: {:?}", e),
+        }
     }
 }
@@ -0,0 +1,474 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::MeshAttribute;
+use crate::geometry::GeometryError;
+use crate::stats::MeshStats;
+use crate::{
+    AttributeDataType, AttributeElement, BufferLayout, DracoDecodeConfig, IndexFormat, OutputMode,
+};
+
+/// What a decoded attribute holds. Indexed variants carry the set index, e.g. `TexCoord(1)`
+/// for `TEXCOORD_1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AttributeSemantic {
+    Position,
+    Normal,
+    Tangent,
+    Color(u32),
+    TexCoord(u32),
+    Joints(u32),
+    Weights(u32),
+    Generic,
+}
+
+impl AttributeSemantic {
+    /// Maps a `draco::GeometryAttribute::Type`, `set` is the number of attributes of the
+    /// same type seen before this one.
+    pub(crate) fn from_draco(attribute_type: i32, set: u32) -> Self {
+        match attribute_type {
+            0 => AttributeSemantic::Position,
+            1 => AttributeSemantic::Normal,
+            2 => AttributeSemantic::Color(set),
+            3 => AttributeSemantic::TexCoord(set),
+            5 => AttributeSemantic::Tangent,
+            7 => AttributeSemantic::Joints(set),
+            8 => AttributeSemantic::Weights(set),
+            _ => AttributeSemantic::Generic,
+        }
+    }
+
+    /// The matching `draco::GeometryAttribute::Type`.
+    pub(crate) fn to_draco(self) -> i32 {
+        match self {
+            AttributeSemantic::Position => 0,
+            AttributeSemantic::Normal => 1,
+            AttributeSemantic::Color(_) => 2,
+            AttributeSemantic::TexCoord(_) => 3,
+            AttributeSemantic::Generic => 4,
+            AttributeSemantic::Tangent => 5,
+            AttributeSemantic::Joints(_) => 7,
+            AttributeSemantic::Weights(_) => 8,
+        }
+    }
+
+    /// Maps a glTF attribute name such as `TEXCOORD_0`; custom `_NAME` attributes are `Generic`.
+    pub fn from_gltf(name: &str) -> Self {
+        let set = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse().ok());
+        match name {
+            "POSITION" => AttributeSemantic::Position,
+            "NORMAL" => AttributeSemantic::Normal,
+            "TANGENT" => AttributeSemantic::Tangent,
+            _ => {
+                if let Some(n) = set("COLOR_") {
+                    AttributeSemantic::Color(n)
+                } else if let Some(n) = set("TEXCOORD_") {
+                    AttributeSemantic::TexCoord(n)
+                } else if let Some(n) = set("JOINTS_") {
+                    AttributeSemantic::Joints(n)
+                } else if let Some(n) = set("WEIGHTS_") {
+                    AttributeSemantic::Weights(n)
+                } else {
+                    AttributeSemantic::Generic
+                }
+            }
+        }
+    }
+}
+
+/// A decoded buffer together with the config describing it.
+#[derive(Debug)]
+pub struct DecodedMesh {
+    data: Vec<u8>,
+    config: DracoDecodeConfig,
+    stats: Option<MeshStats>,
+}
+
+impl DecodedMesh {
+    pub fn new(data: Vec<u8>, config: DracoDecodeConfig) -> Self {
+        Self {
+            data,
+            config,
+            stats: None,
+        }
+    }
+
+    pub fn with_stats(mut self, stats: MeshStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Attribute min/max and position bounds, set by the decode functions. For meshes built
+    /// with [`DecodedMesh::new`] see [`MeshStats::compute`].
+    pub fn stats(&self) -> Option<&MeshStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn config(&self) -> &DracoDecodeConfig {
+        &self.config
+    }
+
+    pub fn layout(&self) -> BufferLayout {
+        self.config.layout()
+    }
+
+    pub fn into_parts(self) -> (Vec<u8>, DracoDecodeConfig) {
+        (self.data, self.config)
+    }
+
+    pub fn vertex_count(&self) -> u32 {
+        self.config.vertex_count()
+    }
+
+    pub fn index_format(&self) -> IndexFormat {
+        self.config.index_format()
+    }
+
+    pub fn index_bytes(&self) -> &[u8] {
+        &self.data[..self.config.index_length() as usize]
+    }
+
+    /// The indices widened to `u32`.
+    pub fn indices(&self) -> Vec<u32> {
+        let bytes = self.index_bytes();
+        match self.index_format() {
+            IndexFormat::U16 => bytemuck::pod_collect_to_vec::<u8, u16>(bytes)
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+            IndexFormat::U32 => bytemuck::pod_collect_to_vec(bytes),
+        }
+    }
+
+    /// Appends an attribute to the buffer and the config, `bytes` holds its tightly packed
+    /// values. Returns the index of the new attribute.
+    pub fn push_attribute(
+        &mut self,
+        semantic: AttributeSemantic,
+        dim: u32,
+        data_type: AttributeDataType,
+        bytes: &[u8],
+    ) -> Result<usize, GeometryError> {
+        let size = dim as usize * data_type.size_in_bytes();
+        if bytes.len() != size * self.vertex_count() as usize {
+            return Err(GeometryError::InvalidLength);
+        }
+        let from = self.layout();
+        self.config.add_semantic_attribute(semantic, dim, data_type);
+        let layout = self.layout();
+        let mut data = layout.repack(&from, &self.data);
+
+        let index = layout.attributes.len() - 1;
+        let attr = &layout.attributes[index];
+        for (i, element) in bytes.chunks_exact(size.max(1)).enumerate() {
+            let start = attr.byte_range.start + i * attr.stride;
+            data[start..start + size].copy_from_slice(element);
+        }
+        self.data = data;
+        self.refresh_stats();
+        Ok(index)
+    }
+
+    /// Gives every index its own vertex, e.g. for flat shading. Afterwards the indices are
+    /// `0..index_count` and every attribute has one element per corner.
+    pub fn split_vertices(&mut self) -> Result<(), GeometryError> {
+        let indices = self.checked_indices()?;
+        let vertices: Vec<usize> = indices.iter().map(|&index| index as usize).collect();
+        self.rebuild(&vertices, 0..indices.len() as u32);
+        self.config.set_output_mode(OutputMode::PerCorner);
+        Ok(())
+    }
+
+    /// Merges vertices whose values are identical in every attribute and drops unused ones,
+    /// the opposite of [`Self::split_vertices`]. Vertices keep their first-use order.
+    /// Returns how many vertices were removed.
+    pub fn weld(&mut self) -> Result<usize, GeometryError> {
+        let indices = self.checked_indices()?;
+        let layout = self.layout();
+        let vertex_bytes = |vertex: usize| -> Vec<u8> {
+            layout
+                .attributes
+                .iter()
+                .flat_map(|attr| {
+                    let start = attr.byte_range.start + vertex * attr.stride;
+                    &self.data[start..start + attr.element_size()]
+                })
+                .copied()
+                .collect()
+        };
+
+        let mut welded: HashMap<Vec<u8>, u32> = HashMap::new();
+        let mut kept = Vec::new();
+        let mut remap = vec![u32::MAX; self.vertex_count() as usize];
+        for &index in &indices {
+            let vertex = index as usize;
+            if remap[vertex] == u32::MAX {
+                remap[vertex] = *welded.entry(vertex_bytes(vertex)).or_insert_with(|| {
+                    kept.push(vertex);
+                    kept.len() as u32 - 1
+                });
+            }
+        }
+
+        let removed = self.vertex_count() as usize - kept.len();
+        self.rebuild(&kept, indices.iter().map(|&index| remap[index as usize]));
+        self.config.set_output_mode(OutputMode::Indexed);
+        Ok(removed)
+    }
+
+    fn checked_indices(&self) -> Result<Vec<u32>, GeometryError> {
+        let indices = self.indices();
+        let vertex_count = self.vertex_count();
+        if indices.iter().any(|&index| index >= vertex_count) {
+            return Err(GeometryError::InvalidIndex);
+        }
+        Ok(indices)
+    }
+
+    /// Replaces the buffer with one where vertex `i` is the old vertex `vertices[i]`.
+    fn rebuild(&mut self, vertices: &[usize], indices: impl Iterator<Item = u32>) {
+        let from = self.layout();
+        let config = self
+            .config
+            .resized(vertices.len() as u32, self.config.index_count());
+        let layout = config.layout();
+        let mut data = vec![0u8; layout.byte_length];
+
+        let index_range = layout.indices.byte_range.clone();
+        match layout.indices.format {
+            IndexFormat::U16 => {
+                let indices: Vec<u16> = indices.map(|index| index as u16).collect();
+                data[index_range].copy_from_slice(bytemuck::cast_slice(&indices));
+            }
+            IndexFormat::U32 => {
+                let indices: Vec<u32> = indices.collect();
+                data[index_range].copy_from_slice(bytemuck::cast_slice(&indices));
+            }
+        }
+        for (src, dst) in from.attributes.iter().zip(&layout.attributes) {
+            let size = dst.element_size();
+            for (to, &vertex) in vertices.iter().enumerate() {
+                let from = src.byte_range.start + vertex * src.stride;
+                let to = dst.byte_range.start + to * dst.stride;
+                data[to..to + size].copy_from_slice(&self.data[from..from + size]);
+            }
+        }
+
+        self.data = data;
+        self.config = config;
+        self.refresh_stats();
+    }
+
+    /// Converts every `Int64`, `UInt64` and `Float64` attribute to `Float32`, e.g. for GPUs
+    /// without 64-bit vertex formats. Normalized integers are dequantized. Returns how many
+    /// attributes were converted.
+    pub fn convert_64bit_to_f32(&mut self) -> usize {
+        let wide: Vec<usize> = self
+            .config
+            .attribute_slice()
+            .iter()
+            .enumerate()
+            .filter(|(_, attr)| attr.data_type().size_in_bytes() == 8)
+            .map(|(index, _)| index)
+            .collect();
+        for &index in &wide {
+            let attr = self.config.attribute_slice()[index];
+            let Some(bytes) = self.packed_attribute_bytes(index) else {
+                continue;
+            };
+            let values = read_f32s(attr.data_type(), &bytes, attr.normalized());
+            self.retype_attribute(
+                index,
+                AttributeDataType::Float32,
+                bytemuck::cast_slice(&values),
+            );
+        }
+        self.refresh_stats();
+        wide.len()
+    }
+
+    /// Replaces attribute `index` with the tightly packed `bytes` of `data_type`, keeping its
+    /// dim.
+    pub(crate) fn retype_attribute(
+        &mut self,
+        index: usize,
+        data_type: AttributeDataType,
+        bytes: &[u8],
+    ) {
+        let from = self.layout();
+        let config = self.config.with_attribute_type(index, data_type);
+        let layout = config.layout();
+        let mut data = vec![0u8; layout.byte_length];
+        data[layout.indices.byte_range.clone()].copy_from_slice(self.index_bytes());
+        for (i, (src, dst)) in from.attributes.iter().zip(&layout.attributes).enumerate() {
+            let size = dst.element_size();
+            for vertex in 0..dst.element_count {
+                let value = if i == index {
+                    &bytes[vertex * size..(vertex + 1) * size]
+                } else {
+                    let start = src.byte_range.start + vertex * src.stride;
+                    &self.data[start..start + size]
+                };
+                let to = dst.byte_range.start + vertex * dst.stride;
+                data[to..to + size].copy_from_slice(value);
+            }
+        }
+        self.data = data;
+        self.config = config;
+    }
+
+    pub(crate) fn refresh_stats(&mut self) {
+        if self.stats.is_some() {
+            self.stats = MeshStats::compute(&self.data, &self.config);
+        }
+    }
+
+    /// The first attribute with `semantic` and its bytes.
+    pub fn attribute(&self, semantic: AttributeSemantic) -> Option<(&MeshAttribute, &[u8])> {
+        let index = self.attribute_index(semantic)?;
+        Some((
+            &self.config.attribute_slice()[index],
+            self.attribute_bytes(index)?,
+        ))
+    }
+
+    pub fn attribute_index(&self, semantic: AttributeSemantic) -> Option<usize> {
+        self.config
+            .attribute_slice()
+            .iter()
+            .position(|attr| attr.semantic() == Some(semantic))
+    }
+
+    /// The bytes of attribute `index`, strided when the mesh was decoded interleaved.
+    pub fn attribute_bytes(&self, index: usize) -> Option<&[u8]> {
+        self.layout().attribute_bytes(&self.data, index)
+    }
+
+    /// The tightly packed bytes of attribute `index`, copied out of interleaved output.
+    pub fn packed_attribute_bytes(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        let layout = self.layout();
+        let attr = layout.attributes.get(index)?;
+        let bytes = self.data.get(attr.byte_range.clone())?;
+        let size = attr.element_size();
+        if attr.stride == size {
+            return Some(Cow::Borrowed(bytes));
+        }
+        let mut packed = Vec::with_capacity(size * attr.element_count);
+        for i in 0..attr.element_count {
+            let start = i * attr.stride;
+            packed.extend_from_slice(&bytes[start..start + size]);
+        }
+        Some(Cow::Owned(packed))
+    }
+
+    /// Positions as `T`, e.g. `glam::Vec3`. Borrowed from the buffer when they are planar
+    /// `Float32` and aligned for `T`, copied otherwise.
+    pub fn positions<T: AttributeElement>(&self) -> Option<Cow<'_, [T]>> {
+        let index = self.attribute_index(AttributeSemantic::Position)?;
+        self.attribute_as(index)
+    }
+
+    pub fn normals<T: AttributeElement>(&self) -> Option<Cow<'_, [T]>> {
+        let index = self.attribute_index(AttributeSemantic::Normal)?;
+        self.attribute_as(index)
+    }
+
+    /// `TexCoord(0)`, integer texcoords are treated as normalized as in glTF.
+    pub fn uvs<T: AttributeElement>(&self) -> Option<Cow<'_, [T]>> {
+        let index = self.attribute_index(AttributeSemantic::TexCoord(0))?;
+        self.read_as(index, true)
+    }
+
+    /// `Color(0)` as RGBA, integer colors are treated as normalized as in glTF and RGB
+    /// colors get an alpha of 1.
+    pub fn colors<T: AttributeElement>(&self) -> Option<Cow<'_, [T]>> {
+        let index = self.attribute_index(AttributeSemantic::Color(0))?;
+        if T::COMPONENTS == 4 && self.config.attribute_slice()[index].dim() == 3 {
+            let rgb: Cow<'_, [[f32; 3]]> = self.read_as(index, true)?;
+            return Some(Cow::Owned(
+                rgb.iter()
+                    .map(|&[r, g, b]| T::from_components(&[r, g, b, 1.0]))
+                    .collect(),
+            ));
+        }
+        self.read_as(index, true)
+    }
+
+    /// Attribute `index` as `T`, `None` unless it has `T::COMPONENTS` components. Integer
+    /// data is converted to `f32`, normalized when [`MeshAttribute::normalized`] is set.
+    pub fn attribute_as<T: AttributeElement>(&self, index: usize) -> Option<Cow<'_, [T]>> {
+        let normalized = self.config.attribute_slice().get(index)?.normalized();
+        self.read_as(index, normalized)
+    }
+
+    fn read_as<T: AttributeElement>(&self, index: usize, normalized: bool) -> Option<Cow<'_, [T]>> {
+        let attr = self.config.attribute_slice().get(index)?;
+        if attr.dim() as usize != T::COMPONENTS {
+            return None;
+        }
+        let bytes = self.packed_attribute_bytes(index)?;
+        if attr.data_type() == AttributeDataType::Float32
+            && let Cow::Borrowed(bytes) = bytes
+            && let Some(elements) = T::cast_slice(bytes)
+        {
+            return Some(Cow::Borrowed(elements));
+        }
+        let values = read_f32s(attr.data_type(), &bytes, normalized);
+        Some(Cow::Owned(
+            values
+                .chunks_exact(T::COMPONENTS)
+                .map(T::from_components)
+                .collect(),
+        ))
+    }
+}
+
+/// Reads every component as `f32`, mapping integers to [0, 1] / [-1, 1] when `normalized`.
+pub(crate) fn read_f32s(data_type: AttributeDataType, bytes: &[u8], normalized: bool) -> Vec<f32> {
+    match data_type {
+        AttributeDataType::Float32 => bytemuck::pod_collect_to_vec(bytes),
+        _ => read_f64s(data_type, bytes, normalized)
+            .into_iter()
+            .map(|v| v as f32)
+            .collect(),
+    }
+}
+
+/// Like [`read_f32s`], without losing the precision of 64-bit attributes.
+pub(crate) fn read_f64s(data_type: AttributeDataType, bytes: &[u8], normalized: bool) -> Vec<f64> {
+    fn convert<T: bytemuck::Pod>(
+        bytes: &[u8],
+        normalized: bool,
+        max: f64,
+        to_f64: fn(T) -> f64,
+    ) -> Vec<f64> {
+        bytemuck::pod_collect_to_vec::<u8, T>(bytes)
+            .into_iter()
+            .map(|v| {
+                let v = to_f64(v);
+                if normalized { (v / max).max(-1.0) } else { v }
+            })
+            .collect()
+    }
+
+    match data_type {
+        AttributeDataType::Int8 => convert(bytes, normalized, i8::MAX.into(), i8::into),
+        AttributeDataType::UInt8 => convert(bytes, normalized, u8::MAX.into(), u8::into),
+        AttributeDataType::Int16 => convert(bytes, normalized, i16::MAX.into(), i16::into),
+        AttributeDataType::UInt16 => convert(bytes, normalized, u16::MAX.into(), u16::into),
+        AttributeDataType::Int32 => convert(bytes, normalized, i32::MAX.into(), i32::into),
+        AttributeDataType::UInt32 => convert(bytes, normalized, u32::MAX.into(), u32::into),
+        AttributeDataType::Int64 => convert(bytes, normalized, i64::MAX as f64, |v: i64| v as f64),
+        AttributeDataType::UInt64 => convert(bytes, normalized, u64::MAX as f64, |v: u64| v as f64),
+        AttributeDataType::Float32 => convert(bytes, false, 1.0, f32::into),
+        AttributeDataType::Float64 => bytemuck::pod_collect_to_vec(bytes),
+    }
+}
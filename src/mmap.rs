@@ -0,0 +1,218 @@
+//! Decoding large meshes and point clouds from a memory-mapped file into a memory-mapped
+//! output file, so neither the bitstream nor the decoded buffer has to be resident. The
+//! output starts with a header describing its config, followed by the buffer as
+//! [`BufferLayout`] describes it; [`MappedMesh`] reads it back.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{
+    AttributeDataType, AttributeSemantic, BufferLayout, DecodedMesh, DracoDecodeConfig,
+    IndexFormat, MeshStats, OutputMode, StreamError, VertexLayout, decode_mesh_into,
+};
+
+const MAGIC: &[u8; 8] = b"DRCOMESH";
+const VERSION: u32 = 1;
+/// Magic, version, data offset, vertex and index count, vertex layout, output mode,
+/// attribute count and index format.
+const FIXED_HEADER_SIZE: usize = 29;
+/// Dim, data type, normalized, semantic and semantic set.
+const ATTRIBUTE_HEADER_SIZE: usize = 11;
+/// The buffer starts at a multiple of this, so 64-bit attributes can be read in place.
+const DATA_ALIGN: usize = 16;
+
+/// Decodes the mesh in `input` into a new file at `output`, both memory-mapped.
+///
+/// The header is written last, so a failed decode leaves a file [`MappedMesh::open`]
+/// rejects. `input` must not be modified while it is decoded.
+pub async fn decode_mesh_to_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    config: &DracoDecodeConfig,
+) -> Result<MeshStats, StreamError> {
+    let byte_length = config.checked_output_size()?;
+    let header = encode_header(config);
+
+    let input = File::open(input)?;
+    // SAFETY: the map is only read for the duration of this call, and modifying the file
+    // concurrently is documented as unsupported.
+    let data = unsafe { Mmap::map(&input)? };
+    let output = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output)?;
+    output.set_len((header.len() + byte_length) as u64)?;
+    // SAFETY: the file was just truncated and is only written through this map.
+    let mut out = unsafe { MmapMut::map_mut(&output)? };
+
+    let stats = decode_mesh_into(&data, config, &mut out[header.len()..]).await?;
+    out.flush()?;
+    out[..header.len()].copy_from_slice(&header);
+    out.flush_range(0, header.len())?;
+    Ok(stats)
+}
+
+/// A file written by [`decode_mesh_to_file`], mapped read-only.
+#[derive(Debug)]
+pub struct MappedMesh {
+    map: Mmap,
+    config: DracoDecodeConfig,
+    data_offset: usize,
+}
+
+impl MappedMesh {
+    /// Maps `path` and parses its header. Fails with [`io::ErrorKind::InvalidData`] if it
+    /// isn't a complete file written by [`decode_mesh_to_file`].
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: modifying the file while it is mapped is documented as unsupported.
+        let map = unsafe { Mmap::map(&file)? };
+        let (config, data_offset) = decode_header(&map).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid mapped mesh header")
+        })?;
+        let end = config
+            .checked_buffer_size()
+            .and_then(|size| size.checked_add(data_offset));
+        if end.is_none_or(|end| map.len() < end) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "mapped mesh is truncated",
+            ));
+        }
+        Ok(Self {
+            map,
+            config,
+            data_offset,
+        })
+    }
+
+    pub fn config(&self) -> &DracoDecodeConfig {
+        &self.config
+    }
+
+    pub fn layout(&self) -> BufferLayout {
+        self.config.layout()
+    }
+
+    /// The decoded buffer, 16 byte aligned.
+    pub fn data(&self) -> &[u8] {
+        &self.map[self.data_offset..self.data_offset + self.config.estimate_buffer_size()]
+    }
+
+    /// Copies the buffer into a [`DecodedMesh`], without stats.
+    pub fn to_decoded_mesh(&self) -> DecodedMesh {
+        DecodedMesh::new(self.data().to_vec(), self.config.clone())
+    }
+}
+
+fn encode_header(config: &DracoDecodeConfig) -> Vec<u8> {
+    let attributes = config.attribute_slice();
+    let len = FIXED_HEADER_SIZE + attributes.len() * ATTRIBUTE_HEADER_SIZE;
+    let data_offset = len.next_multiple_of(DATA_ALIGN);
+
+    let mut header = Vec::with_capacity(data_offset);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&(data_offset as u32).to_le_bytes());
+    header.extend_from_slice(&config.vertex_count().to_le_bytes());
+    header.extend_from_slice(&config.index_count().to_le_bytes());
+    header.push(match config.vertex_layout() {
+        VertexLayout::Planar => 0,
+        VertexLayout::Interleaved => 1,
+    });
+    header.push(match config.output_mode() {
+        OutputMode::Indexed => 0,
+        OutputMode::PerCorner => 1,
+    });
+    header.extend_from_slice(&(attributes.len() as u16).to_le_bytes());
+    header.push(match config.index_format() {
+        IndexFormat::U16 => 0,
+        IndexFormat::U32 => 1,
+    });
+    for attr in attributes {
+        let (semantic, set) = match attr.semantic() {
+            None => (0, 0),
+            Some(AttributeSemantic::Position) => (1, 0),
+            Some(AttributeSemantic::Normal) => (2, 0),
+            Some(AttributeSemantic::Tangent) => (3, 0),
+            Some(AttributeSemantic::Color(set)) => (4, set),
+            Some(AttributeSemantic::TexCoord(set)) => (5, set),
+            Some(AttributeSemantic::Joints(set)) => (6, set),
+            Some(AttributeSemantic::Weights(set)) => (7, set),
+            Some(AttributeSemantic::Generic) => (8, 0),
+        };
+        header.extend_from_slice(&attr.dim().to_le_bytes());
+        header.push(attr.data_type().to_draco() as u8);
+        header.push(attr.normalized() as u8);
+        header.push(semantic);
+        header.extend_from_slice(&set.to_le_bytes());
+    }
+    header.resize(data_offset, 0);
+    header
+}
+
+fn decode_header(data: &[u8]) -> Option<(DracoDecodeConfig, usize)> {
+    let u32_at = |offset: usize| {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    if data.get(..MAGIC.len())? != MAGIC || u32_at(8)? != VERSION {
+        return None;
+    }
+    let data_offset = u32_at(12)? as usize;
+    let mut config = DracoDecodeConfig::new(u32_at(16)?, u32_at(20)?);
+    config.set_vertex_layout(match data.get(24)? {
+        0 => VertexLayout::Planar,
+        1 => VertexLayout::Interleaved,
+        _ => return None,
+    });
+    config.set_output_mode(match data.get(25)? {
+        0 => OutputMode::Indexed,
+        1 => OutputMode::PerCorner,
+        _ => return None,
+    });
+    let attribute_count = u16::from_le_bytes([*data.get(26)?, *data.get(27)?]) as usize;
+    config.set_index_format(match data.get(28)? {
+        0 => IndexFormat::U16,
+        1 => IndexFormat::U32,
+        _ => return None,
+    });
+    if FIXED_HEADER_SIZE + attribute_count * ATTRIBUTE_HEADER_SIZE > data_offset
+        || !data_offset.is_multiple_of(DATA_ALIGN)
+    {
+        return None;
+    }
+
+    for index in 0..attribute_count {
+        let offset = FIXED_HEADER_SIZE + index * ATTRIBUTE_HEADER_SIZE;
+        let dim = u32_at(offset)?;
+        let data_type = AttributeDataType::from_draco(*data.get(offset + 4)? as i32)?;
+        let normalized = *data.get(offset + 5)? != 0;
+        let set = u32_at(offset + 7)?;
+        let semantic = match data.get(offset + 6)? {
+            0 => None,
+            1 => Some(AttributeSemantic::Position),
+            2 => Some(AttributeSemantic::Normal),
+            3 => Some(AttributeSemantic::Tangent),
+            4 => Some(AttributeSemantic::Color(set)),
+            5 => Some(AttributeSemantic::TexCoord(set)),
+            6 => Some(AttributeSemantic::Joints(set)),
+            7 => Some(AttributeSemantic::Weights(set)),
+            8 => Some(AttributeSemantic::Generic),
+            _ => return None,
+        };
+        match semantic {
+            Some(semantic) => config.add_semantic_attribute(semantic, dim, data_type),
+            None => config.add_attribute(dim, data_type),
+        }
+        config.set_attribute_normalized(index, normalized);
+    }
+    Some((config, data_offset))
+}
@@ -0,0 +1,74 @@
+//! Joints and weights of skinned meshes. glTF stores joints as `u8` or `u16` and weights as
+//! floats or normalized integers, which don't sum to exactly 1 once quantized.
+
+use crate::geometry::GeometryError;
+use crate::mesh::read_f32s;
+use crate::{AttributeDataType, AttributeSemantic, DecodedMesh};
+
+impl DecodedMesh {
+    /// Converts every `Joints(n)` attribute to `UInt16` and every `Weights(n)` attribute to
+    /// `Float32` weights that sum to 1 per vertex. Integer weights are dequantized as
+    /// normalized, as in glTF. Both need 4 components, other dims are reported as
+    /// [`GeometryError::UnsupportedDim`] before anything is converted.
+    pub fn normalize_skinning(&mut self) -> Result<(), GeometryError> {
+        let skinning: Vec<(usize, AttributeSemantic)> = self
+            .config()
+            .attribute_slice()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, attr)| match attr.semantic()? {
+                semantic @ (AttributeSemantic::Joints(_) | AttributeSemantic::Weights(_)) => {
+                    Some((index, semantic))
+                }
+                _ => None,
+            })
+            .collect();
+        for &(index, semantic) in &skinning {
+            let dim = self.config().attribute_slice()[index].dim();
+            if dim != 4 {
+                return Err(GeometryError::UnsupportedDim { semantic, dim });
+            }
+        }
+
+        for (index, semantic) in skinning {
+            let attr = self.config().attribute_slice()[index];
+            let bytes = self
+                .packed_attribute_bytes(index)
+                .ok_or(GeometryError::InvalidLength)?
+                .into_owned();
+            if let AttributeSemantic::Joints(_) = semantic {
+                let joints = read_f32s(attr.data_type(), &bytes, false)
+                    .into_iter()
+                    .map(|joint| {
+                        u16::try_from(joint as i64).map_err(|_| GeometryError::InvalidIndex)
+                    })
+                    .collect::<Result<Vec<u16>, _>>()?;
+                self.retype_attribute(
+                    index,
+                    AttributeDataType::UInt16,
+                    bytemuck::cast_slice(&joints),
+                );
+            } else {
+                let mut weights = read_f32s(attr.data_type(), &bytes, true);
+                normalize_weights(&mut weights);
+                self.retype_attribute(
+                    index,
+                    AttributeDataType::Float32,
+                    bytemuck::cast_slice(&weights),
+                );
+            }
+        }
+        self.refresh_stats();
+        Ok(())
+    }
+}
+
+/// Scales every 4 weights to sum to 1, leaving all-zero weights alone.
+pub(crate) fn normalize_weights(weights: &mut [f32]) {
+    for vertex in weights.chunks_exact_mut(4) {
+        let sum: f32 = vertex.iter().sum();
+        if sum > 0.0 {
+            vertex.iter_mut().for_each(|weight| *weight /= sum);
+        }
+    }
+}
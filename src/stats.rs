@@ -0,0 +1,149 @@
+//! Per-attribute statistics and position bounds, gathered while the attributes are written.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::read_f64s;
+use crate::{AttributeSemantic, DracoDecodeConfig};
+
+/// Component-wise statistics of an attribute, in the values as written to the output, i.e.
+/// after conversion to the configured data type and before normalization. Empty for a mesh
+/// without vertices.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttributeStats {
+    /// The glTF accessor `min`.
+    pub min: Vec<f64>,
+    /// The glTF accessor `max`.
+    pub max: Vec<f64>,
+    pub mean: Vec<f64>,
+}
+
+impl AttributeStats {
+    pub(crate) fn from_sums(min: Vec<f64>, max: Vec<f64>, sum: Vec<f64>, count: u32) -> Self {
+        let mean = sum.into_iter().map(|sum| sum / count as f64).collect();
+        Self { min, max, mean }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| (self.min[i] + self.max[i]) * 0.5)
+    }
+
+    pub fn half_extents(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| (self.max[i] - self.min[i]) * 0.5)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BoundingSphere {
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+/// Bounds of a 3 component position attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MeshBounds {
+    pub aabb: Aabb,
+    /// The sphere around [`Self::aabb`], centered on it. It can be computed in the same pass
+    /// as the AABB but is looser than a minimal sphere.
+    pub sphere: BoundingSphere,
+    /// The mean of all positions.
+    pub centroid: [f32; 3],
+}
+
+/// Statistics of a decoded mesh, see [`crate::DecodedMesh::stats`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MeshStats {
+    /// One entry per attribute of the config, in attribute order.
+    pub attributes: Vec<AttributeStats>,
+    /// `None` without a 3 component `Position` attribute or without vertices.
+    pub bounds: Option<MeshBounds>,
+}
+
+impl MeshStats {
+    pub(crate) fn new(config: &DracoDecodeConfig, attributes: Vec<AttributeStats>) -> Self {
+        let bounds = config
+            .attribute_slice()
+            .iter()
+            .position(|attr| attr.semantic() == Some(AttributeSemantic::Position))
+            .and_then(|index| attributes.get(index))
+            .and_then(MeshBounds::from_stats);
+        Self { attributes, bounds }
+    }
+
+    /// Computes the statistics of `data` laid out as `config`, for buffers that weren't
+    /// written by the native decoder.
+    pub fn compute(data: &[u8], config: &DracoDecodeConfig) -> Option<Self> {
+        let layout = config.layout();
+        let count = config.vertex_count();
+        let mut attributes = Vec::with_capacity(layout.attributes.len());
+        for (attr, attr_layout) in config.attribute_slice().iter().zip(&layout.attributes) {
+            let bytes = data.get(attr_layout.byte_range.clone())?;
+            let size = attr_layout.element_size();
+            let packed: Vec<u8> = (0..count as usize)
+                .flat_map(|i| &bytes[i * attr_layout.stride..i * attr_layout.stride + size])
+                .copied()
+                .collect();
+            let values = read_f64s(attr.data_type(), &packed, false);
+
+            let dim = attr_layout.component_count;
+            let (mut min, mut max, mut sum) = if count == 0 {
+                (Vec::new(), Vec::new(), Vec::new())
+            } else {
+                (
+                    vec![f64::INFINITY; dim],
+                    vec![f64::NEG_INFINITY; dim],
+                    vec![0.0; dim],
+                )
+            };
+            for element in values.chunks_exact(dim.max(1)) {
+                for (c, &value) in element.iter().enumerate() {
+                    min[c] = min[c].min(value);
+                    max[c] = max[c].max(value);
+                    sum[c] += value;
+                }
+            }
+            attributes.push(AttributeStats::from_sums(min, max, sum, count));
+        }
+        Some(Self::new(config, attributes))
+    }
+}
+
+impl MeshBounds {
+    fn from_stats(stats: &AttributeStats) -> Option<Self> {
+        if stats.min.len() != 3 {
+            return None;
+        }
+        let vec3 = |values: &[f64]| [0, 1, 2].map(|i| values[i] as f32);
+        let aabb = Aabb {
+            min: vec3(&stats.min),
+            max: vec3(&stats.max),
+        };
+        let radius = aabb
+            .half_extents()
+            .iter()
+            .map(|e| e * e)
+            .sum::<f32>()
+            .sqrt();
+        Some(Self {
+            aabb,
+            sphere: BoundingSphere {
+                center: aabb.center(),
+                radius,
+            },
+            centroid: vec3(&stats.mean),
+        })
+    }
+}
@@ -0,0 +1,238 @@
+//! Decoding meshes that arrive in chunks, e.g. from a network tile loader. Draco can only
+//! decode a complete bitstream, but the header is validated as soon as its bytes arrive, so
+//! bad input is rejected early and output buffers can be allocated from the config's
+//! [`BufferLayout`] while the body is still downloading.
+//!
+//! Files are better decoded with [`decode_mesh_from_path`], which maps them instead of
+//! reading them into memory.
+
+use std::fmt;
+use std::io::{self, Read};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use crate::header::HEADER_SIZE;
+use crate::{
+    BufferLayout, DecodeError, DecodedMesh, DracoDecodeConfig, DracoHeader, GeometryType,
+    decode_mesh_with_stats, inspect_header,
+};
+
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "failed to read draco bitstream: {err}"),
+            StreamError::Decode(err) => write!(f, "failed to decode draco bitstream: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        StreamError::Io(err)
+    }
+}
+
+impl From<DecodeError> for StreamError {
+    fn from(err: DecodeError) -> Self {
+        StreamError::Decode(err)
+    }
+}
+
+/// Progress of a [`DracoStreamDecoder`], see [`DracoStreamDecoder::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamStatus {
+    /// The header hasn't fully arrived yet.
+    NeedHeader,
+    /// The header is valid, `received` bytes of the bitstream have arrived so far.
+    Receiving {
+        header: DracoHeader,
+        received: usize,
+    },
+    /// All of the expected length has arrived, see [`DracoStreamDecoder::with_expected_len`].
+    Complete { header: DracoHeader },
+}
+
+/// Buffers a mesh bitstream fed in chunks and decodes it once complete.
+///
+/// Draco bitstreams don't store their own length, so the decoder only reports
+/// [`StreamStatus::Complete`] if the length is known up front, e.g. from a `Content-Length`
+/// header. Otherwise call [`finish`](Self::finish) once the input ends.
+#[derive(Debug, Clone)]
+pub struct DracoStreamDecoder {
+    config: DracoDecodeConfig,
+    data: Vec<u8>,
+    expected_len: Option<usize>,
+    header: Option<DracoHeader>,
+}
+
+impl DracoStreamDecoder {
+    pub fn new(config: DracoDecodeConfig) -> Self {
+        Self {
+            config,
+            data: Vec::new(),
+            expected_len: None,
+            header: None,
+        }
+    }
+
+    /// Sets the total length of the bitstream and reserves space for it.
+    pub fn with_expected_len(mut self, expected_len: usize) -> Self {
+        self.data
+            .reserve(expected_len.saturating_sub(self.data.len()));
+        self.expected_len = Some(expected_len);
+        self
+    }
+
+    /// Appends the next chunk of the bitstream. Fails once the header has arrived if it isn't
+    /// a Draco mesh, or if the config's output would exceed its
+    /// [`DecodeLimits`](crate::DecodeLimits).
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), DecodeError> {
+        self.data.extend_from_slice(chunk);
+        if self.header.is_none() && self.data.len() >= HEADER_SIZE {
+            self.header = Some(self.check_header()?);
+        }
+        Ok(())
+    }
+
+    pub fn poll(&self) -> StreamStatus {
+        let Some(header) = self.header else {
+            return StreamStatus::NeedHeader;
+        };
+        match self.expected_len {
+            Some(expected_len) if self.data.len() >= expected_len => {
+                StreamStatus::Complete { header }
+            }
+            _ => StreamStatus::Receiving {
+                header,
+                received: self.data.len(),
+            },
+        }
+    }
+
+    pub fn header(&self) -> Option<&DracoHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn config(&self) -> &DracoDecodeConfig {
+        &self.config
+    }
+
+    /// The layout of the decoded buffer, known before any of the body has arrived.
+    pub fn layout(&self) -> BufferLayout {
+        self.config.layout()
+    }
+
+    pub fn received(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Decodes everything fed so far. A truncated bitstream fails as
+    /// [`DecodeError::InvalidBitstream`].
+    pub async fn finish(self) -> Result<DecodedMesh, DecodeError> {
+        if self.header.is_none() {
+            return Err(DecodeError::InvalidBitstream);
+        }
+        decode_mesh_with_stats(&self.data, &self.config).await
+    }
+
+    fn check_header(&self) -> Result<DracoHeader, DecodeError> {
+        let header = inspect_header(&self.data).ok_or(DecodeError::InvalidBitstream)?;
+        if header.geometry_type() != GeometryType::TriangularMesh {
+            return Err(DecodeError::UnexpectedGeometryType);
+        }
+        self.config.checked_output_size()?;
+        Ok(header)
+    }
+}
+
+/// Reads a mesh bitstream to the end and decodes it, failing as soon as the header is
+/// rejected rather than after reading everything.
+pub async fn decode_mesh_from_reader<R: Read>(
+    mut reader: R,
+    config: &DracoDecodeConfig,
+) -> Result<DecodedMesh, StreamError> {
+    let mut decoder = DracoStreamDecoder::new(config.clone());
+    let mut chunk = vec![0; READ_CHUNK_SIZE];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        decoder.feed(&chunk[..read])?;
+    }
+    Ok(decoder.finish().await?)
+}
+
+/// Like [`decode_mesh_from_reader`] for a `tokio::io::AsyncRead`, e.g. a network socket.
+#[cfg(feature = "tokio")]
+pub async fn decode_mesh_from_async_read<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+    config: &DracoDecodeConfig,
+) -> Result<DecodedMesh, StreamError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut decoder = DracoStreamDecoder::new(config.clone());
+    let mut chunk = vec![0; READ_CHUNK_SIZE];
+    loop {
+        let read = match reader.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        decoder.feed(&chunk[..read])?;
+    }
+    Ok(decoder.finish().await?)
+}
+
+/// Like [`decode_mesh_from_reader`] for a `futures::io::AsyncRead`, for runtimes other than
+/// tokio.
+#[cfg(feature = "futures")]
+pub async fn decode_mesh_from_futures_read<R: futures_io::AsyncRead + Unpin>(
+    mut reader: R,
+    config: &DracoDecodeConfig,
+) -> Result<DecodedMesh, StreamError> {
+    use std::pin::Pin;
+
+    let mut decoder = DracoStreamDecoder::new(config.clone());
+    let mut chunk = vec![0; READ_CHUNK_SIZE];
+    loop {
+        let read = std::future::poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut chunk));
+        let read = match read.await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        decoder.feed(&chunk[..read])?;
+    }
+    Ok(decoder.finish().await?)
+}
+
+/// Decodes a mesh file by memory-mapping it, so the bitstream is never copied into memory
+/// and only the pages Draco reads are loaded.
+///
+/// The file must not be modified while it is decoded.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_mesh_from_path(
+    path: impl AsRef<Path>,
+    config: &DracoDecodeConfig,
+) -> Result<DecodedMesh, StreamError> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the map is only read for the duration of this call, and truncating or writing
+    // the file concurrently is documented as unsupported.
+    let data = unsafe { memmap2::Mmap::map(&file)? };
+    Ok(decode_mesh_with_stats(&data, config).await?)
+}
@@ -0,0 +1,454 @@
+//! Cesium 3D Tiles payloads: `.pnts` point clouds, including the
+//! `3DTILES_draco_point_compression` extension, and the headers of `.b3dm` / `.i3dm` tiles
+//! around their embedded glTF.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{AttributeValues, DecodeError};
+
+const DRACO_EXTENSION: &str = "3DTILES_draco_point_compression";
+
+#[derive(Debug)]
+pub enum TileError {
+    /// Wrong magic or version, or table lengths that run past the end of the tile.
+    InvalidHeader,
+    InvalidJson(String),
+    /// A feature table property is missing, of the wrong type or outside the binary body.
+    InvalidProperty(String),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for TileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileError::InvalidHeader => write!(f, "invalid 3D Tiles header"),
+            TileError::InvalidJson(err) => write!(f, "invalid 3D Tiles table JSON: {err}"),
+            TileError::InvalidProperty(name) => write!(f, "invalid 3D Tiles property {name}"),
+            TileError::Decode(err) => write!(f, "failed to decode draco payload: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TileError {}
+
+impl From<DecodeError> for TileError {
+    fn from(err: DecodeError) -> Self {
+        TileError::Decode(err)
+    }
+}
+
+/// The feature and batch tables every tile format starts with.
+#[derive(Debug, Clone)]
+pub struct TileTables<'a> {
+    pub feature_table: Value,
+    pub feature_table_binary: &'a [u8],
+    pub batch_table: Option<Value>,
+    pub batch_table_binary: &'a [u8],
+}
+
+/// A `.b3dm` tile, `glb` is the embedded binary glTF.
+#[derive(Debug, Clone)]
+pub struct Batched3dModel<'a> {
+    pub tables: TileTables<'a>,
+    pub glb: &'a [u8],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileGltf<'a> {
+    Glb(&'a [u8]),
+    Uri(String),
+}
+
+/// A `.i3dm` tile, whose glTF is either embedded or referenced by URI.
+#[derive(Debug, Clone)]
+pub struct Instanced3dModel<'a> {
+    pub tables: TileTables<'a>,
+    pub gltf: TileGltf<'a>,
+}
+
+/// A named batch table property stored in the Draco payload of a `.pnts` tile.
+#[derive(Debug)]
+pub struct BatchProperty {
+    pub name: String,
+    /// Components per point.
+    pub dim: u32,
+    pub values: AttributeValues,
+}
+
+/// A decoded `.pnts` tile. Colors are RGBA, `RGB` tiles get an alpha of 255.
+#[derive(Debug)]
+pub struct PointsTile {
+    pub points_length: u32,
+    pub positions: Vec<[f32; 3]>,
+    pub colors: Option<Vec<[u8; 4]>>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    /// Positions are relative to this center when set.
+    pub rtc_center: Option<[f64; 3]>,
+    /// Properties from the batch table's Draco extension, the rest of the batch table is
+    /// left in [`Self::batch_table`].
+    pub batch_properties: Vec<BatchProperty>,
+    pub feature_table: Value,
+    pub batch_table: Option<Value>,
+}
+
+/// Splits a tile into its tables and the body after them. `header_length` is 28 for
+/// `.pnts` and `.b3dm` and 32 for `.i3dm`.
+fn parse_tile<'a>(
+    data: &'a [u8],
+    magic: &[u8; 4],
+    header_length: usize,
+) -> Result<(TileTables<'a>, &'a [u8]), TileError> {
+    let word = |index: usize| {
+        data.get(index * 4..index * 4 + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    if data.len() < header_length || &data[..4] != magic || word(1) != Some(1) {
+        return Err(TileError::InvalidHeader);
+    }
+    let byte_length = word(2).ok_or(TileError::InvalidHeader)?;
+    let data = data.get(..byte_length).ok_or(TileError::InvalidHeader)?;
+
+    let mut offset = header_length;
+    let mut take = |length: usize| {
+        let start = offset;
+        offset = offset.checked_add(length)?;
+        data.get(start..offset)
+    };
+    let lengths = [3, 4, 5, 6].map(|index| word(index).unwrap_or(0));
+    let [feature_json, feature_binary, batch_json, batch_binary] =
+        lengths.map(|length| take(length).ok_or(TileError::InvalidHeader));
+    let tables = TileTables {
+        feature_table: parse_json(feature_json?)?,
+        feature_table_binary: feature_binary?,
+        batch_table: match batch_json? {
+            [] => None,
+            json => Some(parse_json(json)?),
+        },
+        batch_table_binary: batch_binary?,
+    };
+    Ok((tables, &data[offset..]))
+}
+
+fn parse_json(bytes: &[u8]) -> Result<Value, TileError> {
+    // Tables are padded with spaces, some writers pad with zeros.
+    let end = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    serde_json::from_slice(&bytes[..end]).map_err(|err| TileError::InvalidJson(err.to_string()))
+}
+
+pub fn parse_b3dm(data: &[u8]) -> Result<Batched3dModel<'_>, TileError> {
+    let (tables, glb) = parse_tile(data, b"b3dm", 28)?;
+    Ok(Batched3dModel { tables, glb })
+}
+
+pub fn parse_i3dm(data: &[u8]) -> Result<Instanced3dModel<'_>, TileError> {
+    let (tables, body) = parse_tile(data, b"i3dm", 32)?;
+    let gltf = match u32::from_le_bytes(data[28..32].try_into().unwrap()) {
+        0 => {
+            let uri = String::from_utf8_lossy(body);
+            TileGltf::Uri(uri.trim_end_matches([' ', '\0']).to_string())
+        }
+        1 => TileGltf::Glb(body),
+        _ => return Err(TileError::InvalidHeader),
+    };
+    Ok(Instanced3dModel { tables, gltf })
+}
+
+/// Decodes a `.pnts` tile. Draco compressed tiles and uncompressed `POSITION`,
+/// `POSITION_QUANTIZED`, `RGBA`, `RGB` and `NORMAL` are supported, also alongside Draco for
+/// the properties it doesn't compress; other per-point properties such as `RGB565` are left
+/// in the feature table. Native only, like
+/// [`crate::decode_mesh_auto`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn decode_pnts(data: &[u8], limits: &crate::DecodeLimits) -> Result<PointsTile, TileError> {
+    let (tables, _) = parse_tile(data, b"pnts", 28)?;
+    let feature_table = &tables.feature_table;
+    let points_length = feature_table
+        .get("POINTS_LENGTH")
+        .and_then(Value::as_u64)
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| TileError::InvalidProperty("POINTS_LENGTH".to_string()))?;
+    let rtc_center = match feature_table.get("RTC_CENTER") {
+        Some(value) => Some(read_vec3_f64(
+            value,
+            tables.feature_table_binary,
+            "RTC_CENTER",
+        )?),
+        None => None,
+    };
+
+    let points = match feature_table
+        .get("extensions")
+        .and_then(|e| e.get(DRACO_EXTENSION))
+    {
+        Some(extension) => decode_draco_points(extension, &tables, points_length, limits)?,
+        None => read_points(feature_table, tables.feature_table_binary, points_length)?,
+    };
+    if points.positions.len() != points_length as usize {
+        return Err(TileError::InvalidProperty("POINTS_LENGTH".to_string()));
+    }
+    Ok(PointsTile {
+        points_length,
+        positions: points.positions,
+        colors: points.colors,
+        normals: points.normals,
+        rtc_center,
+        batch_properties: points.batch_properties,
+        feature_table: tables.feature_table,
+        batch_table: tables.batch_table,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PointData {
+    positions: Vec<[f32; 3]>,
+    colors: Option<Vec<[u8; 4]>>,
+    normals: Option<Vec<[f32; 3]>>,
+    batch_properties: Vec<BatchProperty>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_draco_points(
+    extension: &Value,
+    tables: &TileTables<'_>,
+    points_length: u32,
+    limits: &crate::DecodeLimits,
+) -> Result<PointData, TileError> {
+    let invalid = |name: &str| TileError::InvalidProperty(format!("{DRACO_EXTENSION}.{name}"));
+    let offset = extension.get("byteOffset").and_then(Value::as_u64);
+    let length = extension.get("byteLength").and_then(Value::as_u64);
+    let payload = offset
+        .zip(length)
+        .and_then(|(offset, length)| {
+            let start = usize::try_from(offset).ok()?;
+            let end = start.checked_add(usize::try_from(length).ok()?)?;
+            tables.feature_table_binary.get(start..end)
+        })
+        .ok_or_else(|| invalid("byteLength"))?;
+
+    let (mesh, unique_ids) = crate::ffi::decode_geometry_auto_native(payload, limits)?;
+    let attribute = |properties: Option<&Value>, name: &str| -> Result<Option<usize>, TileError> {
+        let Some(id) = properties.and_then(|p| p.get(name)) else {
+            return Ok(None);
+        };
+        let id = id.as_u64().ok_or_else(|| invalid(name))?;
+        unique_ids
+            .iter()
+            .position(|&unique_id| unique_id as u64 == id)
+            .map(Some)
+            .ok_or_else(|| invalid(name))
+    };
+    let vec3 = |index: usize, name: &str| {
+        mesh.attribute_as::<[f32; 3]>(index)
+            .map(|values| values.into_owned())
+            .ok_or_else(|| invalid(name))
+    };
+
+    // Properties the extension doesn't list stay uncompressed in the feature table.
+    let (feature_table, binary) = (&tables.feature_table, tables.feature_table_binary);
+    let count = points_length as usize;
+    let properties = extension.get("properties");
+    let positions = match attribute(properties, "POSITION")? {
+        Some(index) => vec3(index, "POSITION")?,
+        None => read_positions(feature_table, binary, count)?
+            .ok_or_else(|| TileError::InvalidProperty("POSITION".to_string()))?,
+    };
+    let normals = match attribute(properties, "NORMAL")? {
+        Some(index) => Some(vec3(index, "NORMAL")?),
+        None => read_normals(feature_table, binary, count)?,
+    };
+    let color = match attribute(properties, "RGBA")? {
+        Some(index) => Some((index, "RGBA")),
+        None => attribute(properties, "RGB")?.map(|index| (index, "RGB")),
+    };
+    let colors = match color {
+        Some((index, name)) => {
+            let attr = &mesh.config().attribute_slice()[index];
+            let bytes = mesh
+                .packed_attribute_bytes(index)
+                .ok_or_else(|| invalid(name))?;
+            let values = crate::mesh::read_f32s(attr.data_type(), &bytes, true);
+            let values: Vec<u8> = values.iter().map(|v| (v * 255.0).round() as u8).collect();
+            Some(rgba(&values, attr.dim() as usize).ok_or_else(|| invalid(name))?)
+        }
+        None => read_colors(feature_table, binary, count)?,
+    };
+
+    let batch_properties = tables.batch_table.as_ref().and_then(|table| {
+        table
+            .get("extensions")?
+            .get(DRACO_EXTENSION)?
+            .get("properties")
+    });
+    let mut batch = Vec::new();
+    if let Some(names) = batch_properties.and_then(Value::as_object) {
+        for name in names.keys() {
+            let index = attribute(batch_properties, name)?.ok_or_else(|| invalid(name))?;
+            let attr = &mesh.config().attribute_slice()[index];
+            let bytes = mesh
+                .packed_attribute_bytes(index)
+                .ok_or_else(|| invalid(name))?;
+            batch.push(BatchProperty {
+                name: name.clone(),
+                dim: attr.dim(),
+                values: AttributeValues::from_bytes(attr.data_type(), &bytes),
+            });
+        }
+    }
+
+    Ok(PointData {
+        positions,
+        colors,
+        normals,
+        batch_properties: batch,
+    })
+}
+
+/// Reads uncompressed per-point properties from the feature table binary.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_points(
+    feature_table: &Value,
+    binary: &[u8],
+    points_length: u32,
+) -> Result<PointData, TileError> {
+    let count = points_length as usize;
+    Ok(PointData {
+        positions: read_positions(feature_table, binary, count)?
+            .ok_or_else(|| TileError::InvalidProperty("POSITION".to_string()))?,
+        colors: read_colors(feature_table, binary, count)?,
+        normals: read_normals(feature_table, binary, count)?,
+        batch_properties: Vec::new(),
+    })
+}
+
+/// The bytes of `count` values of the per-point property `name`, `None` if the feature
+/// table doesn't have it.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_property<'a>(
+    feature_table: &Value,
+    binary: &'a [u8],
+    name: &str,
+    count: usize,
+    element_size: usize,
+) -> Result<Option<&'a [u8]>, TileError> {
+    let Some(value) = feature_table.get(name) else {
+        return Ok(None);
+    };
+    let start = value.get("byteOffset").and_then(Value::as_u64);
+    start
+        .and_then(|start| {
+            let start = usize::try_from(start).ok()?;
+            binary.get(start..start.checked_add(count.checked_mul(element_size)?)?)
+        })
+        .map(Some)
+        .ok_or_else(|| TileError::InvalidProperty(name.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn floats(bytes: &[u8]) -> Vec<[f32; 3]> {
+    bytemuck::pod_collect_to_vec::<u8, f32>(bytes)
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect()
+}
+
+/// `POSITION`, or `POSITION_QUANTIZED` scaled into the quantized volume.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_positions(
+    feature_table: &Value,
+    binary: &[u8],
+    count: usize,
+) -> Result<Option<Vec<[f32; 3]>>, TileError> {
+    if let Some(bytes) = read_property(feature_table, binary, "POSITION", count, 12)? {
+        return Ok(Some(floats(bytes)));
+    }
+    let Some(bytes) = read_property(feature_table, binary, "POSITION_QUANTIZED", count, 6)? else {
+        return Ok(None);
+    };
+    let offset = read_vec3_f64(
+        feature_table
+            .get("QUANTIZED_VOLUME_OFFSET")
+            .unwrap_or(&Value::Null),
+        binary,
+        "QUANTIZED_VOLUME_OFFSET",
+    )?;
+    let scale = read_vec3_f64(
+        feature_table
+            .get("QUANTIZED_VOLUME_SCALE")
+            .unwrap_or(&Value::Null),
+        binary,
+        "QUANTIZED_VOLUME_SCALE",
+    )?;
+    Ok(Some(
+        bytemuck::pod_collect_to_vec::<u8, u16>(bytes)
+            .chunks_exact(3)
+            .map(|q| [0, 1, 2].map(|i| (offset[i] + q[i] as f64 / 65535.0 * scale[i]) as f32))
+            .collect(),
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_colors(
+    feature_table: &Value,
+    binary: &[u8],
+    count: usize,
+) -> Result<Option<Vec<[u8; 4]>>, TileError> {
+    Ok(
+        if let Some(bytes) = read_property(feature_table, binary, "RGBA", count, 4)? {
+            rgba(bytes, 4)
+        } else if let Some(bytes) = read_property(feature_table, binary, "RGB", count, 3)? {
+            rgba(bytes, 3)
+        } else {
+            None
+        },
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_normals(
+    feature_table: &Value,
+    binary: &[u8],
+    count: usize,
+) -> Result<Option<Vec<[f32; 3]>>, TileError> {
+    Ok(read_property(feature_table, binary, "NORMAL", count, 12)?.map(floats))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn rgba(values: &[u8], dim: usize) -> Option<Vec<[u8; 4]>> {
+    match dim {
+        3 => Some(
+            values
+                .chunks_exact(3)
+                .map(|c| [c[0], c[1], c[2], 255])
+                .collect(),
+        ),
+        4 => Some(
+            values
+                .chunks_exact(4)
+                .map(|c| [c[0], c[1], c[2], c[3]])
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// A global feature table property, either a JSON array or a `byteOffset` into the binary.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_vec3_f64(value: &Value, binary: &[u8], name: &str) -> Result<[f64; 3], TileError> {
+    let invalid = || TileError::InvalidProperty(name.to_string());
+    if let Some(array) = value.as_array() {
+        let values: Vec<f64> = array.iter().filter_map(Value::as_f64).collect();
+        return <[f64; 3]>::try_from(values).map_err(|_| invalid());
+    }
+    let start = value
+        .get("byteOffset")
+        .and_then(Value::as_u64)
+        .and_then(|start| usize::try_from(start).ok())
+        .ok_or_else(invalid)?;
+    let bytes = binary
+        .get(start..start.checked_add(12).ok_or_else(invalid)?)
+        .ok_or_else(invalid)?;
+    let values: Vec<f32> = bytemuck::pod_collect_to_vec(bytes);
+    Ok([0, 1, 2].map(|i| values[i] as f64))
+}
@@ -0,0 +1,97 @@
+//! Where decoding spends its time, see [`crate::decode_mesh_with_timings`]. With the
+//! `tracing` feature every phase is also a `tracing` span.
+
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Time spent in each phase of a decode. Dequantization isn't timed on its own: Draco runs
+/// it inside attribute decoders it creates and keeps to itself, so natively it is part of
+/// `attributes`. On wasm all of Draco's work happens in the worker, reported as `worker`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeTimings {
+    /// Parsing and checking the header, including Draco's own pass over it and the metadata.
+    pub header: Duration,
+    /// Draco decoding the faces, or the point count of a point cloud.
+    pub connectivity: Duration,
+    /// Draco decoding and dequantizing the attributes.
+    pub attributes: Duration,
+    /// Checking the decoded geometry against the config and limits.
+    pub validate: Duration,
+    /// Converting and writing the indices and attributes.
+    pub write: Duration,
+    /// The round trip to the wasm worker, including copying the bitstream and output.
+    pub worker: Duration,
+    /// Rearranging the worker's output into the config's layout and output mode.
+    pub repack: Duration,
+}
+
+impl DecodeTimings {
+    pub fn total(&self) -> Duration {
+        self.header
+            + self.connectivity
+            + self.attributes
+            + self.validate
+            + self.write
+            + self.worker
+            + self.repack
+    }
+}
+
+impl AddAssign for DecodeTimings {
+    fn add_assign(&mut self, other: Self) {
+        self.header += other.header;
+        self.connectivity += other.connectivity;
+        self.attributes += other.attributes;
+        self.validate += other.validate;
+        self.write += other.write;
+        self.worker += other.worker;
+        self.repack += other.repack;
+    }
+}
+
+/// Measures consecutive phases. `Instant` panics on `wasm32-unknown-unknown`, so wasm uses
+/// `Date.now()`, with millisecond resolution.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    last: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    last: f64,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self { last: Self::now() }
+    }
+
+    /// The time since the start or the previous lap.
+    pub(crate) fn lap(&mut self) -> Duration {
+        let now = Self::now();
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed = now - self.last;
+        #[cfg(target_arch = "wasm32")]
+        let elapsed = Duration::from_secs_f64((now - self.last).max(0.0) / 1000.0);
+        self.last = now;
+        elapsed
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now() -> std::time::Instant {
+        std::time::Instant::now()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now() -> f64 {
+        js_sys::Date::now()
+    }
+}
+
+/// Enters an info level `tracing` span until the end of the enclosing block with the
+/// `tracing` feature, and does nothing without it.
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+
+pub(crate) use trace_span;
@@ -0,0 +1,345 @@
+//! Connectivity of triangle meshes: positions-only decoding and a corner table for
+//! adjacency queries.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{DecodedMesh, GeometryError};
+
+const INVALID: u32 = u32::MAX;
+
+/// Faces and positions only, see [`crate::decode_connectivity`]. Vertices are the unique
+/// position values, so faces that share a position are connected even when other attributes
+/// have seams there.
+///
+/// Edgebreaker meshes keep the corner table Draco builds while decoding them, whose vertices
+/// are the ones the encoder connected, with non-manifold vertices and edges split.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectivityMesh {
+    pub indices: Vec<u32>,
+    pub positions: Vec<[f32; 3]>,
+    /// Draco's opposite corners, empty without a corner table from Draco.
+    opposites: Vec<u32>,
+}
+
+impl ConnectivityMesh {
+    pub(crate) fn new(indices: Vec<u32>, positions: Vec<[f32; 3]>, opposites: Vec<u32>) -> Self {
+        Self {
+            indices,
+            positions,
+            opposites,
+        }
+    }
+
+    /// Draco's corner table for edgebreaker meshes, built from the indices otherwise.
+    pub fn corner_table(&self) -> Result<CornerTable, GeometryError> {
+        if self.opposites.is_empty() {
+            CornerTable::new(&self.indices, self.positions.len())
+        } else {
+            CornerTable::with_opposites(&self.indices, &self.opposites, self.positions.len())
+        }
+    }
+}
+
+impl DecodedMesh {
+    /// A corner table over the decoded indices. Vertices split at attribute seams are not
+    /// connected, use [`crate::decode_connectivity`] for the topology of the surface.
+    pub fn corner_table(&self) -> Result<CornerTable, GeometryError> {
+        CornerTable::new(&self.indices(), self.vertex_count() as usize)
+    }
+}
+
+/// Corner table of a triangle list, with corners `3 * face + 0..3` as in Draco. Edges
+/// shared by more than two faces, or by two faces with the same winding, are non-manifold
+/// and have no opposite corners.
+#[derive(Debug, Clone)]
+pub struct CornerTable {
+    corner_vertices: Vec<u32>,
+    opposites: Vec<u32>,
+    /// Corners of vertex `v` are `vertex_corners[vertex_offsets[v]..vertex_offsets[v + 1]]`.
+    vertex_offsets: Vec<usize>,
+    vertex_corners: Vec<u32>,
+    non_manifold_edges: Vec<[u32; 2]>,
+}
+
+impl CornerTable {
+    /// Builds the table for `indices`, ignoring a trailing partial face. Fails with
+    /// [`GeometryError::InvalidIndex`] unless every index is below `vertex_count`.
+    pub fn new(indices: &[u32], vertex_count: usize) -> Result<Self, GeometryError> {
+        let corner_vertices = indices[..indices.len() / 3 * 3].to_vec();
+        check_indices(&corner_vertices, vertex_count)?;
+        let edge = |corner: usize| {
+            let face = corner / 3 * 3;
+            [
+                corner_vertices[face + (corner + 1) % 3],
+                corner_vertices[face + (corner + 2) % 3],
+            ]
+        };
+
+        // Each corner is opposite the directed edge from its next to its previous vertex.
+        let mut directed: HashMap<[u32; 2], u32> = HashMap::with_capacity(corner_vertices.len());
+        let mut non_manifold: HashSet<[u32; 2]> = HashSet::new();
+        let undirected = |[a, b]: [u32; 2]| [a.min(b), a.max(b)];
+        for corner in 0..corner_vertices.len() {
+            let edge = edge(corner);
+            if directed.insert(edge, corner as u32).is_some() {
+                non_manifold.insert(undirected(edge));
+            }
+        }
+
+        let opposites = (0..corner_vertices.len())
+            .map(|corner| {
+                let [a, b] = edge(corner);
+                if a == b || non_manifold.contains(&undirected([a, b])) {
+                    return INVALID;
+                }
+                directed.get(&[b, a]).copied().unwrap_or(INVALID)
+            })
+            .collect();
+
+        let mut non_manifold_edges: Vec<[u32; 2]> = non_manifold.into_iter().collect();
+        non_manifold_edges.sort_unstable();
+        Ok(Self::from_parts(
+            corner_vertices,
+            opposites,
+            non_manifold_edges,
+            vertex_count,
+        ))
+    }
+
+    /// A table with known opposite corners, `u32::MAX` for none, such as Draco's, which
+    /// has no non-manifold edges.
+    pub(crate) fn with_opposites(
+        indices: &[u32],
+        opposites: &[u32],
+        vertex_count: usize,
+    ) -> Result<Self, GeometryError> {
+        let corners = indices.len();
+        if !corners.is_multiple_of(3) || opposites.len() != corners {
+            return Err(GeometryError::InvalidIndex);
+        }
+        check_indices(indices, vertex_count)?;
+        let table = Self::from_parts(
+            indices.to_vec(),
+            opposites.to_vec(),
+            Vec::new(),
+            vertex_count,
+        );
+        // Opposites have to pair up across the same edge, or walking fans might not end.
+        let consistent = (0..corners).all(|corner| {
+            table.opposite(corner).is_none_or(|opposite| {
+                opposite < corners
+                    && table.opposites[opposite] as usize == corner
+                    && table.vertex(table.next(corner)) == table.vertex(table.previous(opposite))
+                    && table.vertex(table.previous(corner)) == table.vertex(table.next(opposite))
+            })
+        });
+        if !consistent {
+            return Err(GeometryError::InvalidIndex);
+        }
+        Ok(table)
+    }
+
+    fn from_parts(
+        corner_vertices: Vec<u32>,
+        opposites: Vec<u32>,
+        non_manifold_edges: Vec<[u32; 2]>,
+        vertex_count: usize,
+    ) -> Self {
+        let mut vertex_offsets = vec![0; vertex_count + 1];
+        for &vertex in &corner_vertices {
+            vertex_offsets[vertex as usize + 1] += 1;
+        }
+        for v in 0..vertex_count {
+            vertex_offsets[v + 1] += vertex_offsets[v];
+        }
+        let mut fill = vertex_offsets.clone();
+        let mut vertex_corners = vec![0; corner_vertices.len()];
+        for (corner, &vertex) in corner_vertices.iter().enumerate() {
+            vertex_corners[fill[vertex as usize]] = corner as u32;
+            fill[vertex as usize] += 1;
+        }
+
+        Self {
+            corner_vertices,
+            opposites,
+            vertex_offsets,
+            vertex_corners,
+            non_manifold_edges,
+        }
+    }
+
+    pub fn num_corners(&self) -> usize {
+        self.corner_vertices.len()
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.corner_vertices.len() / 3
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.vertex_offsets.len() - 1
+    }
+
+    pub fn vertex(&self, corner: usize) -> u32 {
+        self.corner_vertices[corner]
+    }
+
+    pub fn face(&self, corner: usize) -> usize {
+        corner / 3
+    }
+
+    pub fn next(&self, corner: usize) -> usize {
+        if corner % 3 == 2 {
+            corner - 2
+        } else {
+            corner + 1
+        }
+    }
+
+    pub fn previous(&self, corner: usize) -> usize {
+        if corner.is_multiple_of(3) {
+            corner + 2
+        } else {
+            corner - 1
+        }
+    }
+
+    /// The corner across the edge opposite `corner`, `None` on boundary and non-manifold
+    /// edges.
+    pub fn opposite(&self, corner: usize) -> Option<usize> {
+        match self.opposites[corner] {
+            INVALID => None,
+            opposite => Some(opposite as usize),
+        }
+    }
+
+    /// The corner of the same vertex in the neighbouring face across the edge to the next
+    /// vertex.
+    pub fn swing_right(&self, corner: usize) -> Option<usize> {
+        self.opposite(self.previous(corner))
+            .map(|opposite| self.previous(opposite))
+    }
+
+    /// The corner of the same vertex in the neighbouring face across the edge to the
+    /// previous vertex.
+    pub fn swing_left(&self, corner: usize) -> Option<usize> {
+        self.opposite(self.next(corner))
+            .map(|opposite| self.next(opposite))
+    }
+
+    /// Every corner of `vertex`, in face order.
+    pub fn vertex_corners(&self, vertex: u32) -> impl Iterator<Item = usize> + '_ {
+        let v = vertex as usize;
+        self.vertex_corners[self.vertex_offsets[v]..self.vertex_offsets[v + 1]]
+            .iter()
+            .map(|&corner| corner as usize)
+    }
+
+    /// The neighbours of `vertex` in winding order, starting at a boundary for open fans.
+    /// Only the fan around the vertex's first corner is walked, see
+    /// [`Self::is_vertex_manifold`].
+    pub fn vertex_ring(&self, vertex: u32) -> Vec<u32> {
+        let Some((first, closed, _)) = self.fan(vertex) else {
+            return Vec::new();
+        };
+        let mut ring = Vec::new();
+        if !closed {
+            ring.push(self.vertex(self.previous(first)));
+        }
+        let mut corner = first;
+        loop {
+            ring.push(self.vertex(self.next(corner)));
+            match self.swing_right(corner) {
+                Some(right) if right != first => corner = right,
+                _ => break,
+            }
+        }
+        ring
+    }
+
+    /// Whether the faces around `vertex` form a single fan. Unused vertices are manifold.
+    pub fn is_vertex_manifold(&self, vertex: u32) -> bool {
+        let v = vertex as usize;
+        let count = self.vertex_offsets[v + 1] - self.vertex_offsets[v];
+        self.fan(vertex).is_none_or(|(_, _, size)| size == count)
+    }
+
+    /// Whether `vertex` lies on a boundary edge.
+    pub fn is_boundary_vertex(&self, vertex: u32) -> bool {
+        self.vertex_corners(vertex)
+            .any(|corner| self.swing_left(corner).is_none())
+    }
+
+    /// Edges with a single face, from the face's winding.
+    pub fn boundary_edges(&self) -> Vec<[u32; 2]> {
+        (0..self.num_corners())
+            .filter(|&corner| self.opposite(corner).is_none())
+            .map(|corner| {
+                [
+                    self.vertex(self.next(corner)),
+                    self.vertex(self.previous(corner)),
+                ]
+            })
+            .filter(|&[a, b]| {
+                a != b
+                    && self
+                        .non_manifold_edges
+                        .binary_search(&[a.min(b), a.max(b)])
+                        .is_err()
+            })
+            .collect()
+    }
+
+    /// Edges shared by more than two faces or by faces with the same winding, as sorted
+    /// vertex pairs.
+    pub fn non_manifold_edges(&self) -> &[[u32; 2]] {
+        &self.non_manifold_edges
+    }
+
+    /// No non-manifold edges and no vertices joining separate fans.
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges.is_empty()
+            && (0..self.num_vertices() as u32).all(|vertex| self.is_vertex_manifold(vertex))
+    }
+
+    /// Manifold without boundary edges.
+    pub fn is_closed(&self) -> bool {
+        self.is_manifold() && self.opposites.iter().all(|&opposite| opposite != INVALID)
+    }
+
+    /// The first corner of the fan around the first corner of `vertex`, whether the fan is
+    /// closed and how many corners it has.
+    fn fan(&self, vertex: u32) -> Option<(usize, bool, usize)> {
+        let start = self.vertex_corners(vertex).next()?;
+        let mut first = start;
+        while let Some(left) = self.swing_left(first) {
+            if left == start {
+                let size = self.fan_size(start);
+                return Some((start, true, size));
+            }
+            first = left;
+        }
+        Some((first, false, self.fan_size(first)))
+    }
+
+    fn fan_size(&self, first: usize) -> usize {
+        let mut size = 1;
+        let mut corner = first;
+        while let Some(right) = self.swing_right(corner) {
+            if right == first {
+                break;
+            }
+            corner = right;
+            size += 1;
+        }
+        size
+    }
+}
+
+fn check_indices(indices: &[u32], vertex_count: usize) -> Result<(), GeometryError> {
+    if indices.iter().all(|&index| (index as usize) < vertex_count) {
+        Ok(())
+    } else {
+        Err(GeometryError::InvalidIndex)
+    }
+}
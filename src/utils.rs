@@ -1,4 +1,13 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::DecodeError;
+use crate::layout::{BufferLayout, IndexFormat, OutputMode, VertexLayout};
+use crate::mesh::AttributeSemantic;
+use crate::validation::ValidationMode;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AttributeDataType {
     Int8,
     UInt8,
@@ -7,6 +16,9 @@ pub enum AttributeDataType {
     Int32,
     UInt32,
     Float32,
+    Int64,
+    UInt64,
+    Float64,
 }
 
 impl AttributeDataType {
@@ -15,6 +27,40 @@ impl AttributeDataType {
             AttributeDataType::Int8 | AttributeDataType::UInt8 => 1,
             AttributeDataType::Int16 | AttributeDataType::UInt16 => 2,
             AttributeDataType::Int32 | AttributeDataType::UInt32 | AttributeDataType::Float32 => 4,
+            AttributeDataType::Int64 | AttributeDataType::UInt64 | AttributeDataType::Float64 => 8,
+        }
+    }
+
+    /// Maps a `draco::DataType` value.
+    pub(crate) fn from_draco(data_type: i32) -> Option<Self> {
+        match data_type {
+            1 => Some(AttributeDataType::Int8),
+            2 => Some(AttributeDataType::UInt8),
+            3 => Some(AttributeDataType::Int16),
+            4 => Some(AttributeDataType::UInt16),
+            5 => Some(AttributeDataType::Int32),
+            6 => Some(AttributeDataType::UInt32),
+            7 => Some(AttributeDataType::Int64),
+            8 => Some(AttributeDataType::UInt64),
+            9 => Some(AttributeDataType::Float32),
+            10 => Some(AttributeDataType::Float64),
+            _ => None,
+        }
+    }
+
+    /// The matching `draco::DataType` value.
+    pub(crate) fn to_draco(self) -> i32 {
+        match self {
+            AttributeDataType::Int8 => 1,
+            AttributeDataType::UInt8 => 2,
+            AttributeDataType::Int16 => 3,
+            AttributeDataType::UInt16 => 4,
+            AttributeDataType::Int32 => 5,
+            AttributeDataType::UInt32 => 6,
+            AttributeDataType::Float32 => 9,
+            AttributeDataType::Int64 => 7,
+            AttributeDataType::UInt64 => 8,
+            AttributeDataType::Float64 => 10,
         }
     }
 }
@@ -25,13 +71,23 @@ pub struct MeshAttribute {
     data_type: AttributeDataType,
     offset: u32,
     lenght: u32,
+    semantic: Option<AttributeSemantic>,
+    normalized: bool,
+    unique_id: Option<u32>,
 }
 
 impl MeshAttribute {
+    /// Offset in planar output, see [`BufferLayout`] for other vertex layouts and for
+    /// buffers of 4 GiB or more, where this and [`Self::length`] saturate.
     pub fn offset(&self) -> u32 {
         self.offset
     }
 
+    pub fn length(&self) -> u32 {
+        self.lenght
+    }
+
+    #[deprecated(note = "use `length`")]
     pub fn lenght(&self) -> u32 {
         self.lenght
     }
@@ -43,29 +99,103 @@ impl MeshAttribute {
     pub fn dim(&self) -> u32 {
         self.dim
     }
+
+    /// `None` for attributes added without one through [`DracoDecodeConfig::add_attribute`].
+    pub fn semantic(&self) -> Option<AttributeSemantic> {
+        self.semantic
+    }
+
+    /// Whether integer values represent [0, 1] / [-1, 1], as in glTF accessors.
+    pub fn normalized(&self) -> bool {
+        self.normalized
+    }
+
+    /// The Draco attribute this is decoded from, see
+    /// [`DracoDecodeConfig::set_attribute_unique_id`]. `None` takes them in order.
+    pub fn unique_id(&self) -> Option<u32> {
+        self.unique_id
+    }
 }
 
-#[derive(Debug)]
+/// Upper bounds enforced while decoding untrusted input. Everything is unlimited by default.
+///
+/// Point and face counts are checked against what the bitstream declares before Draco
+/// decodes it, and against the decoded geometry after. Edgebreaker meshes declare their
+/// vertices before attribute seams split them, so for those `max_faces` bounds the memory
+/// Draco allocates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DecodeLimits {
+    max_points: Option<u32>,
+    max_faces: Option<u32>,
+    max_output_bytes: Option<usize>,
+}
+
+impl DecodeLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_points(mut self, max_points: u32) -> Self {
+        self.max_points = Some(max_points);
+        self
+    }
+
+    pub fn with_max_faces(mut self, max_faces: u32) -> Self {
+        self.max_faces = Some(max_faces);
+        self
+    }
+
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = Some(max_output_bytes);
+        self
+    }
+
+    pub fn max_points(&self) -> Option<u32> {
+        self.max_points
+    }
+
+    pub fn max_faces(&self) -> Option<u32> {
+        self.max_faces
+    }
+
+    pub fn max_output_bytes(&self) -> Option<usize> {
+        self.max_output_bytes
+    }
+
+    pub(crate) fn allows_output_bytes(&self, len: usize) -> bool {
+        self.max_output_bytes.is_none_or(|max| len <= max)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DracoDecodeConfig {
     vertex_count: u32,
     index_count: u32,
     index_length: u32,
+    index_format: IndexFormat,
     attributes: Vec<MeshAttribute>,
+    limits: DecodeLimits,
+    validation: ValidationMode,
+    vertex_layout: VertexLayout,
+    output_mode: OutputMode,
 }
 
 impl DracoDecodeConfig {
     pub fn new(vertex_count: u32, index_count: u32) -> Self {
-        let index_length = if index_count <= u16::MAX as u32 {
-            index_count as usize * 2
-        } else {
-            index_count as usize * 4
-        } as u32;
+        let index_format = IndexFormat::for_index_count(index_count);
+        let index_length = byte_size(&[index_count as usize, index_format.size_in_bytes()]);
 
         Self {
             vertex_count,
             index_count,
             index_length,
+            index_format,
             attributes: Vec::new(),
+            limits: DecodeLimits::default(),
+            validation: ValidationMode::default(),
+            vertex_layout: VertexLayout::default(),
+            output_mode: OutputMode::default(),
         }
     }
 
@@ -73,18 +203,144 @@ impl DracoDecodeConfig {
         self.index_length
     }
 
+    pub fn vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+
+    pub fn index_format(&self) -> IndexFormat {
+        self.index_format
+    }
+
+    /// Writes indices as `format` instead of the narrowest one that fits, e.g. `U32` for
+    /// meshes that are merged later. `U16` is ignored if the index count doesn't fit.
+    pub fn set_index_format(&mut self, format: IndexFormat) {
+        self.index_format = match format {
+            IndexFormat::U16 => IndexFormat::for_index_count(self.index_count),
+            IndexFormat::U32 => IndexFormat::U32,
+        };
+        self.index_length =
+            byte_size(&[self.index_count as usize, self.index_format.size_in_bytes()]);
+        self.update_offsets();
+    }
+
     pub fn add_attribute(&mut self, dim: u32, data_type: AttributeDataType) {
-        let offset = self.estimate_buffer_size() as u32;
-        let lenght = dim * self.vertex_count * data_type.size_in_bytes() as u32;
+        self.push_attribute(dim, data_type, None);
+    }
+
+    pub fn add_semantic_attribute(
+        &mut self,
+        semantic: AttributeSemantic,
+        dim: u32,
+        data_type: AttributeDataType,
+    ) {
+        self.push_attribute(dim, data_type, Some(semantic));
+    }
+
+    /// Adds the attribute of the glTF accessor `name`, e.g. `JOINTS_0`, with its semantic
+    /// from [`AttributeSemantic::from_gltf`] and the accessor's `normalized` flag.
+    pub fn add_gltf_attribute(
+        &mut self,
+        name: &str,
+        dim: u32,
+        data_type: AttributeDataType,
+        normalized: bool,
+    ) {
+        self.push_attribute(dim, data_type, Some(AttributeSemantic::from_gltf(name)));
+        self.set_attribute_normalized(self.attributes.len() - 1, normalized);
+    }
+
+    fn push_attribute(
+        &mut self,
+        dim: u32,
+        data_type: AttributeDataType,
+        semantic: Option<AttributeSemantic>,
+    ) {
+        let offset = self.attributes.last().map_or(self.index_length, |attr| {
+            attr.offset.saturating_add(attr.lenght)
+        });
+        let lenght = byte_size(&[
+            dim as usize,
+            self.vertex_count as usize,
+            data_type.size_in_bytes(),
+        ]);
         let attribute = MeshAttribute {
             dim,
             data_type,
             offset,
             lenght,
+            semantic,
+            normalized: false,
+            unique_id: None,
         };
         self.attributes.push(attribute);
     }
 
+    /// The same config for `vertex_count` vertices and `index_count` indices.
+    pub(crate) fn resized(&self, vertex_count: u32, index_count: u32) -> Self {
+        let mut config = Self::new(vertex_count, index_count);
+        config.set_index_format(self.index_format);
+        config.limits = self.limits;
+        config.validation = self.validation;
+        config.vertex_layout = self.vertex_layout;
+        config.output_mode = self.output_mode;
+        for attr in &self.attributes {
+            config.push_attribute(attr.dim, attr.data_type, attr.semantic);
+            let index = config.attributes.len() - 1;
+            config.set_attribute_normalized(index, attr.normalized);
+            config.attributes[index].unique_id = attr.unique_id;
+        }
+        config
+    }
+
+    /// The same config with attribute `index` stored as non-normalized `data_type`.
+    pub(crate) fn with_attribute_type(&self, index: usize, data_type: AttributeDataType) -> Self {
+        let mut config = self.clone();
+        if let Some(attr) = config.attributes.get_mut(index) {
+            attr.data_type = data_type;
+            attr.normalized = false;
+        }
+        config.update_offsets();
+        config
+    }
+
+    fn update_offsets(&mut self) {
+        let mut offset = self.index_length;
+        for attr in &mut self.attributes {
+            attr.offset = offset;
+            attr.lenght = byte_size(&[
+                attr.dim as usize,
+                self.vertex_count as usize,
+                attr.data_type.size_in_bytes(),
+            ]);
+            offset = offset.saturating_add(attr.lenght);
+        }
+    }
+
+    pub fn set_attribute_normalized(&mut self, index: usize, normalized: bool) {
+        if let Some(attr) = self.attributes.get_mut(index) {
+            attr.normalized = normalized;
+        }
+    }
+
+    /// Decodes attribute `index` from the Draco attribute with `unique_id` instead of the
+    /// one at the same position in the bitstream, which is sorted by unique id. This is how
+    /// glTF's `KHR_draco_mesh_compression` references attributes, and allows decoding only
+    /// some of them in any order.
+    ///
+    /// On wasm the worker only writes attributes in bitstream order, so there `unique_id`
+    /// has to be `index` and anything else fails with [`ConfigMismatch::UniqueId`].
+    ///
+    /// [`ConfigMismatch::UniqueId`]: crate::ConfigMismatch::UniqueId
+    pub fn set_attribute_unique_id(&mut self, index: usize, unique_id: u32) {
+        if let Some(attr) = self.attributes.get_mut(index) {
+            attr.unique_id = Some(unique_id);
+        }
+    }
+
     pub fn get_attribute(&self, index: usize) -> Option<&MeshAttribute> {
         self.attributes.get(index)
     }
@@ -92,29 +348,100 @@ impl DracoDecodeConfig {
     pub fn attributes(&self) -> Vec<MeshAttribute> {
         self.attributes.clone()
     }
+
+    /// The attributes in output order, without copying them like [`Self::attributes`].
+    pub fn attribute_slice(&self) -> &[MeshAttribute] {
+        &self.attributes
+    }
+
+    pub fn layout(&self) -> BufferLayout {
+        BufferLayout::new(self)
+    }
+
+    pub fn set_limits(&mut self, limits: DecodeLimits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    pub fn set_validation(&mut self, validation: ValidationMode) {
+        self.validation = validation;
+    }
+
+    pub fn validation(&self) -> ValidationMode {
+        self.validation
+    }
+
+    pub fn set_vertex_layout(&mut self, vertex_layout: VertexLayout) {
+        self.vertex_layout = vertex_layout;
+    }
+
+    pub fn vertex_layout(&self) -> VertexLayout {
+        self.vertex_layout
+    }
+
+    /// With [`OutputMode::PerCorner`] the vertex count has to equal the index count.
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        self.output_mode = output_mode;
+    }
+
+    pub fn output_mode(&self) -> OutputMode {
+        self.output_mode
+    }
 }
 
 impl DracoDecodeConfig {
+    /// Saturates at `usize::MAX` if the buffer can't be addressed, see
+    /// [`Self::checked_buffer_size`].
     pub fn estimate_buffer_size(&self) -> usize {
-        let mut size = 0;
-
-        let index_bytes = if self.index_count <= u16::MAX as u32 {
-            (self.index_count as usize) * 2
-        } else {
-            (self.index_count as usize) * 4
-        };
-        size += index_bytes;
+        self.checked_buffer_size().unwrap_or(usize::MAX)
+    }
 
-        for attr in &self.attributes {
-            let attr_data_size =
-                (attr.dim as usize) * (self.vertex_count as usize) * attr.data_type.size_in_bytes();
-            size += attr_data_size;
+    /// The size of the decoded buffer, `None` if it doesn't fit in a `usize`.
+    pub fn checked_buffer_size(&self) -> Option<usize> {
+        let vertex_count = self.vertex_count as usize;
+        let index_bytes =
+            (self.index_count as usize).checked_mul(self.index_format.size_in_bytes())?;
+        let mut element_sizes = self
+            .attributes
+            .iter()
+            .map(|attr| (attr.dim as usize).checked_mul(attr.data_type.size_in_bytes()));
+        match self.vertex_layout {
+            VertexLayout::Planar => element_sizes.try_fold(index_bytes, |size, element| {
+                size.checked_add(element?.checked_mul(vertex_count)?)
+            }),
+            VertexLayout::Interleaved => {
+                let vertex_size = element_sizes.try_fold(0usize, |size, element| {
+                    size.checked_add(element?.checked_next_multiple_of(4)?)
+                })?;
+                index_bytes
+                    .checked_next_multiple_of(4)?
+                    .checked_add(vertex_size.checked_mul(vertex_count)?)
+            }
         }
+    }
 
-        size
+    /// The size of the decoded buffer, failing if it can't be addressed or is over the
+    /// output limit.
+    pub(crate) fn checked_output_size(&self) -> Result<usize, DecodeError> {
+        self.checked_buffer_size()
+            .filter(|&size| self.limits.allows_output_bytes(size))
+            .ok_or(DecodeError::LimitExceeded)
     }
 }
 
+/// The product of `factors` for the `u32` sizes of [`MeshAttribute`], saturating at
+/// `u32::MAX` for buffers of 4 GiB or more.
+fn byte_size(factors: &[usize]) -> u32 {
+    factors
+        .iter()
+        .try_fold(1usize, |size, &factor| size.checked_mul(factor))
+        .and_then(|size| u32::try_from(size).ok())
+        .unwrap_or(u32::MAX)
+}
+
 #[derive(Debug)]
 pub enum AttributeValues {
     Int8(Vec<i8>),
@@ -124,4 +451,35 @@ pub enum AttributeValues {
     Int32(Vec<i32>),
     UInt32(Vec<u32>),
     Float32(Vec<f32>),
+    Int64(Vec<i64>),
+    UInt64(Vec<u64>),
+    Float64(Vec<f64>),
+}
+
+impl AttributeValues {
+    /// Copies tightly packed little endian values of `data_type`.
+    pub fn from_bytes(data_type: AttributeDataType, bytes: &[u8]) -> Self {
+        match data_type {
+            AttributeDataType::Int8 => AttributeValues::Int8(bytemuck::pod_collect_to_vec(bytes)),
+            AttributeDataType::UInt8 => AttributeValues::UInt8(bytes.to_vec()),
+            AttributeDataType::Int16 => AttributeValues::Int16(bytemuck::pod_collect_to_vec(bytes)),
+            AttributeDataType::UInt16 => {
+                AttributeValues::UInt16(bytemuck::pod_collect_to_vec(bytes))
+            }
+            AttributeDataType::Int32 => AttributeValues::Int32(bytemuck::pod_collect_to_vec(bytes)),
+            AttributeDataType::UInt32 => {
+                AttributeValues::UInt32(bytemuck::pod_collect_to_vec(bytes))
+            }
+            AttributeDataType::Float32 => {
+                AttributeValues::Float32(bytemuck::pod_collect_to_vec(bytes))
+            }
+            AttributeDataType::Int64 => AttributeValues::Int64(bytemuck::pod_collect_to_vec(bytes)),
+            AttributeDataType::UInt64 => {
+                AttributeValues::UInt64(bytemuck::pod_collect_to_vec(bytes))
+            }
+            AttributeDataType::Float64 => {
+                AttributeValues::Float64(bytemuck::pod_collect_to_vec(bytes))
+            }
+        }
+    }
 }
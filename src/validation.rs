@@ -0,0 +1,178 @@
+use std::fmt;
+
+use crate::error::DecodeError;
+use crate::{AttributeDataType, DracoDecodeConfig};
+
+/// How closely a [`DracoDecodeConfig`] has to match the decoded bitstream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Vertex count, index count, attribute count and every attribute's dim and data type
+    /// must match the bitstream exactly.
+    Strict,
+    /// Counts and dims must match. Attributes are converted to the configured data type,
+    /// and attributes the config doesn't list at the end of the bitstream are skipped.
+    #[default]
+    Lenient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigMismatch {
+    VertexCount {
+        expected: u32,
+        found: u32,
+    },
+    IndexCount {
+        expected: u32,
+        found: u32,
+    },
+    AttributeCount {
+        expected: usize,
+        found: usize,
+    },
+    Attribute {
+        index: usize,
+        expected_dim: u32,
+        expected_type: AttributeDataType,
+        found_dim: u32,
+        /// `None` when the bitstream uses a data type this crate can't represent.
+        found_type: Option<AttributeDataType>,
+    },
+    /// Attribute `index` selects a unique id the bitstream doesn't have. On wasm, which only
+    /// decodes attributes in order, any unique id other than `index`.
+    UniqueId {
+        index: usize,
+        unique_id: u32,
+    },
+    /// Only detectable on wasm, where the decoder reports nothing but the written bytes.
+    OutputLength {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ConfigMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigMismatch::VertexCount { expected, found } => {
+                write!(f, "vertex count: expected {expected}, found {found}")
+            }
+            ConfigMismatch::IndexCount { expected, found } => {
+                write!(f, "index count: expected {expected}, found {found}")
+            }
+            ConfigMismatch::AttributeCount { expected, found } => {
+                write!(f, "attribute count: expected {expected}, found {found}")
+            }
+            ConfigMismatch::Attribute {
+                index,
+                expected_dim,
+                expected_type,
+                found_dim,
+                found_type,
+            } => {
+                write!(
+                    f,
+                    "attribute {index}: expected dim {expected_dim} {expected_type:?}, found dim {found_dim} "
+                )?;
+                match found_type {
+                    Some(found_type) => write!(f, "{found_type:?}"),
+                    None => write!(f, "unsupported type"),
+                }
+            }
+            ConfigMismatch::UniqueId { index, unique_id } => {
+                write!(
+                    f,
+                    "attribute {index}: no attribute with unique id {unique_id}"
+                )
+            }
+            ConfigMismatch::OutputLength { expected, found } => {
+                write!(f, "output length: expected {expected} bytes, found {found}")
+            }
+        }
+    }
+}
+
+/// Layout of one attribute as found in the bitstream.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FoundAttribute {
+    pub unique_id: u32,
+    pub dim: u32,
+    pub data_type: Option<AttributeDataType>,
+}
+
+/// Compares `config` with what was actually decoded, collecting every discrepancy.
+pub(crate) fn validate_config(
+    config: &DracoDecodeConfig,
+    vertex_count: u32,
+    index_count: u32,
+    found: &[FoundAttribute],
+) -> Result<(), DecodeError> {
+    let strict = config.validation() == ValidationMode::Strict;
+    let mut mismatches = Vec::new();
+
+    if config.vertex_count() != vertex_count {
+        mismatches.push(ConfigMismatch::VertexCount {
+            expected: config.vertex_count(),
+            found: vertex_count,
+        });
+    }
+    if config.index_count() != index_count {
+        mismatches.push(ConfigMismatch::IndexCount {
+            expected: config.index_count(),
+            found: index_count,
+        });
+    }
+
+    let expected = config.attribute_slice();
+    if expected.len() > found.len() || (strict && expected.len() != found.len()) {
+        mismatches.push(ConfigMismatch::AttributeCount {
+            expected: expected.len(),
+            found: found.len(),
+        });
+    }
+
+    for (index, (expected, source)) in expected
+        .iter()
+        .zip(attribute_sources(config, found))
+        .enumerate()
+    {
+        let Some(found) = source.map(|source| found[source]) else {
+            if let Some(unique_id) = expected.unique_id() {
+                mismatches.push(ConfigMismatch::UniqueId { index, unique_id });
+            }
+            continue;
+        };
+        let type_matches = !strict || found.data_type == Some(expected.data_type());
+        if expected.dim() != found.dim || !type_matches {
+            mismatches.push(ConfigMismatch::Attribute {
+                index,
+                expected_dim: expected.dim(),
+                expected_type: expected.data_type(),
+                found_dim: found.dim,
+                found_type: found.data_type,
+            });
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(DecodeError::ConfigMismatch(mismatches))
+    }
+}
+
+/// The index in `found` each attribute of `config` is decoded from, by unique id where the
+/// config sets one and by position otherwise. `None` where there is no such attribute.
+pub(crate) fn attribute_sources(
+    config: &DracoDecodeConfig,
+    found: &[FoundAttribute],
+) -> Vec<Option<usize>> {
+    config
+        .attribute_slice()
+        .iter()
+        .enumerate()
+        .map(|(index, attr)| match attr.unique_id() {
+            Some(unique_id) => found.iter().position(|found| found.unique_id == unique_id),
+            None => (index < found.len()).then_some(index),
+        })
+        .collect()
+}
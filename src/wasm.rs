@@ -3,9 +3,12 @@ use std::cell::RefCell;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::window;
 
-use crate::{AttributeDataType, AttributeValues, DracoDecodeConfig};
+use crate::error::DecodeError;
+use crate::layout::{BufferLayout, IndexFormat, OutputMode, VertexLayout};
+use crate::timing::{DecodeTimings, Stopwatch, trace_span};
+use crate::validation::ConfigMismatch;
+use crate::{AttributeDataType, AttributeValues, DecodedMesh, DracoDecodeConfig};
 
 use web_sys::console;
 
@@ -13,13 +16,80 @@ thread_local! {
     static DRACO_DECODE_FUNC_MODULE: RefCell<Option<JsValue>> = RefCell::new(None);
 }
 
+const DECODER_MODULE_JS: &str = include_str!("../javascript/index.es.js");
+const RUNTIME_SHIM_JS: &str = include_str!("../javascript/runtime_shim.js");
+
+/// JavaScript host the wasm module is running in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsRuntime {
+    /// Browser main thread or web worker with `Blob`, `URL` and `Worker`.
+    Browser,
+    /// Node.js, decoding on a `worker_threads` worker.
+    Node,
+    /// Deno, decoding on a module worker.
+    Deno,
+    /// Any other host without workers, decoding in the current isolate.
+    Inline,
+}
+
+impl JsRuntime {
+    fn detect() -> Self {
+        let global = js_sys::global();
+
+        if !get_property(&global, "Deno").is_undefined() {
+            return JsRuntime::Deno;
+        }
+
+        let versions = get_property(&get_property(&global, "process"), "versions");
+        if get_property(&versions, "node").is_string() {
+            return JsRuntime::Node;
+        }
+
+        let has_browser_apis = ["Worker", "Blob", "URL"]
+            .iter()
+            .all(|name| !get_property(&global, name).is_undefined());
+        if has_browser_apis {
+            JsRuntime::Browser
+        } else {
+            JsRuntime::Inline
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            JsRuntime::Browser => "browser",
+            JsRuntime::Node => "node",
+            JsRuntime::Deno => "deno",
+            JsRuntime::Inline => "inline",
+        }
+    }
+}
+
+fn get_property(target: &JsValue, name: &str) -> JsValue {
+    if target.is_undefined() || target.is_null() {
+        return JsValue::UNDEFINED;
+    }
+    js_sys::Reflect::get(target, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+}
+
 async fn get_js_module() -> Result<JsValue, JsValue> {
     if let Some(module) = DRACO_DECODE_FUNC_MODULE.with(|m| m.borrow().clone()) {
         return Ok(module);
     }
 
-    let js_code = include_str!("../javascript/index.es.js");
-    let escaped = js_code.replace("\\", "\\\\").replace("`", "\\`");
+    let module_promise: Promise = match JsRuntime::detect() {
+        JsRuntime::Browser => import_module_from_blob()?,
+        runtime => import_module_with_shim(runtime)?,
+    };
+    let module = JsFuture::from(module_promise).await?;
+
+    DRACO_DECODE_FUNC_MODULE.with(|m| m.replace(Some(module.clone())));
+
+    Ok(module)
+}
+
+fn import_module_from_blob() -> Result<Promise, JsValue> {
+    let escaped = DECODER_MODULE_JS.replace("\\", "\\\\").replace("`", "\\`");
 
     let setup_code = format!(
         r#"
@@ -37,12 +107,17 @@ async fn get_js_module() -> Result<JsValue, JsValue> {
 
     // Use eval to run the wrapper and return a promise of the module
     let js_module = js_sys::eval(&setup_code)?;
-    let module_promise: Promise = js_module.dyn_into()?;
-    let module = JsFuture::from(module_promise).await?;
-
-    DRACO_DECODE_FUNC_MODULE.with(|m| m.replace(Some(module.clone())));
+    js_module.dyn_into()
+}
 
-    Ok(module)
+fn import_module_with_shim(runtime: JsRuntime) -> Result<Promise, JsValue> {
+    let shim: js_sys::Function = js_sys::eval(RUNTIME_SHIM_JS)?.dyn_into()?;
+    let result = shim.call2(
+        &JsValue::NULL,
+        &JsValue::from_str(runtime.name()),
+        &JsValue::from_str(DECODER_MODULE_JS),
+    )?;
+    result.dyn_into()
 }
 
 async fn decode_draco_mesh_from_embedded_js(
@@ -62,15 +137,144 @@ async fn decode_draco_mesh_from_embedded_js(
     Ok(out_buf.dyn_into::<Uint8Array>()?)
 }
 
-pub async fn decode_mesh_wasm_worker(data: &[u8], config: &DracoDecodeConfig) -> Option<Vec<u8>> {
-    let js_array = Uint8Array::from(data);
-    let estimate_buffer_size = config.estimate_buffer_size();
+pub async fn decode_mesh_wasm_worker(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    timings: &mut DecodeTimings,
+) -> Result<Vec<u8>, DecodeError> {
+    check_unique_ids(config)?;
+    if config.output_mode() == OutputMode::PerCorner {
+        return decode_per_corner(data, config, timings).await;
+    }
 
-    match decode_draco_mesh_from_embedded_js(&js_array, estimate_buffer_size).await {
-        Ok(decoded) => Some(decoded.to_vec()),
-        Err(err) => {
-            web_sys::console::error_1(&err);
-            None
-        }
+    config.checked_output_size()?;
+    // The worker always writes planar output with the narrowest indices, which is never
+    // larger than the checked output.
+    let mut worker_config = config.clone();
+    worker_config.set_index_format(IndexFormat::U16);
+    let planar = BufferLayout::with_vertex_layout(&worker_config, VertexLayout::Planar);
+    let estimate_buffer_size = planar.byte_length;
+    let decoded = run_worker(data, estimate_buffer_size, timings).await?;
+
+    let mut stopwatch = Stopwatch::start();
+    trace_span!("repack", output_bytes = config.estimate_buffer_size());
+    // The worker only reports the bytes it wrote, a short write means the config
+    // describes more data than the bitstream holds.
+    if decoded.len() != estimate_buffer_size {
+        return Err(output_length_mismatch(estimate_buffer_size, decoded.len()));
+    }
+    let layout = config.layout();
+    let decoded = if layout != planar {
+        layout.repack(&planar, &decoded)
+    } else {
+        decoded
+    };
+    timings.repack += stopwatch.lap();
+    Ok(decoded)
+}
+
+/// The worker writes a value per point, so per corner output is decoded indexed and
+/// expanded here. The point count is taken from the bytes the worker wrote, with room for
+/// as many points as corners.
+async fn decode_per_corner(
+    data: &[u8],
+    config: &DracoDecodeConfig,
+    timings: &mut DecodeTimings,
+) -> Result<Vec<u8>, DecodeError> {
+    if config.vertex_count() != config.index_count() {
+        return Err(DecodeError::ConfigMismatch(vec![
+            ConfigMismatch::VertexCount {
+                expected: config.vertex_count(),
+                found: config.index_count(),
+            },
+        ]));
     }
+    config.checked_output_size()?;
+
+    let mut indexed = config.clone();
+    indexed.set_output_mode(OutputMode::Indexed);
+    indexed.set_vertex_layout(VertexLayout::Planar);
+    indexed.set_index_format(IndexFormat::U16);
+    let capacity = indexed.layout().byte_length;
+    let decoded = run_worker(data, capacity, timings).await?;
+
+    let mut stopwatch = Stopwatch::start();
+    trace_span!("repack", output_bytes = config.estimate_buffer_size());
+    let index_length = indexed.index_length() as usize;
+    let vertex_size: usize = config
+        .attribute_slice()
+        .iter()
+        .map(|attr| attr.dim() as usize * attr.data_type().size_in_bytes())
+        .sum();
+    let points = decoded
+        .len()
+        .checked_sub(index_length)
+        .filter(|bytes| vertex_size > 0 && bytes % vertex_size == 0)
+        .map(|bytes| bytes / vertex_size)
+        .ok_or_else(|| output_length_mismatch(capacity, decoded.len()))?;
+
+    let indexed = indexed.resized(points as u32, config.index_count());
+    let mut mesh = DecodedMesh::new(decoded, indexed);
+    mesh.split_vertices()
+        .map_err(|_| DecodeError::InvalidBitstream)?;
+    let (buf, split) = mesh.into_parts();
+    let (layout, split_layout) = (config.layout(), split.layout());
+    let buf = if layout != split_layout {
+        layout.repack(&split_layout, &buf)
+    } else {
+        buf
+    };
+    timings.repack += stopwatch.lap();
+    Ok(buf)
+}
+
+async fn run_worker(
+    data: &[u8],
+    byte_length: usize,
+    timings: &mut DecodeTimings,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut stopwatch = Stopwatch::start();
+    let round_trip = async {
+        let js_array = Uint8Array::from(data);
+        let decoded = decode_draco_mesh_from_embedded_js(&js_array, byte_length).await;
+        decoded.map(|decoded| decoded.to_vec())
+    };
+    #[cfg(feature = "tracing")]
+    let round_trip = tracing::Instrument::instrument(
+        round_trip,
+        tracing::info_span!(
+            "worker_round_trip",
+            input_bytes = data.len(),
+            output_bytes = byte_length
+        ),
+    );
+    let decoded = round_trip.await;
+    timings.worker += stopwatch.lap();
+    decoded.map_err(|err| {
+        web_sys::console::error_1(&err);
+        DecodeError::InvalidBitstream
+    })
+}
+
+/// The worker writes attributes in bitstream order, which only matches unique ids that are
+/// the attribute's index.
+fn check_unique_ids(config: &DracoDecodeConfig) -> Result<(), DecodeError> {
+    let mismatches: Vec<_> = config
+        .attribute_slice()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, attr)| {
+            let unique_id = attr.unique_id()?;
+            (unique_id as usize != index).then_some(ConfigMismatch::UniqueId { index, unique_id })
+        })
+        .collect();
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(DecodeError::ConfigMismatch(mismatches))
+    }
+}
+
+fn output_length_mismatch(expected: usize, found: usize) -> DecodeError {
+    DecodeError::ConfigMismatch(vec![ConfigMismatch::OutputLength { expected, found }])
 }
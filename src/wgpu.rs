@@ -0,0 +1,187 @@
+//! Maps decoded buffers to wgpu vertex and index formats.
+
+use std::fmt;
+use std::ops::Range;
+
+use ::wgpu::{VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+
+use crate::{AttributeDataType, AttributeLayout, BufferLayout, IndexFormat, VertexLayout};
+
+/// wgpu requires vertex buffer offsets and strides to be multiples of this.
+const VERTEX_ALIGNMENT: usize = ::wgpu::VERTEX_ALIGNMENT as usize;
+
+/// The wgpu format for `dim` components of `data_type`, `None` where wgpu has none
+/// (3-component 8 and 16 bit formats, normalized 32 bit integers, 64-bit integers, more than
+/// 4 components). `Float64` formats need `Features::VERTEX_ATTRIBUTE_64BIT`, see
+/// [`crate::DecodedMesh::convert_64bit_to_f32`].
+pub fn vertex_format(
+    data_type: AttributeDataType,
+    dim: u32,
+    normalized: bool,
+) -> Option<VertexFormat> {
+    use AttributeDataType::*;
+    use VertexFormat as F;
+
+    let format = match (data_type, normalized, dim) {
+        (Int8, false, 1) => F::Sint8,
+        (Int8, false, 2) => F::Sint8x2,
+        (Int8, false, 4) => F::Sint8x4,
+        (Int8, true, 1) => F::Snorm8,
+        (Int8, true, 2) => F::Snorm8x2,
+        (Int8, true, 4) => F::Snorm8x4,
+        (UInt8, false, 1) => F::Uint8,
+        (UInt8, false, 2) => F::Uint8x2,
+        (UInt8, false, 4) => F::Uint8x4,
+        (UInt8, true, 1) => F::Unorm8,
+        (UInt8, true, 2) => F::Unorm8x2,
+        (UInt8, true, 4) => F::Unorm8x4,
+        (Int16, false, 1) => F::Sint16,
+        (Int16, false, 2) => F::Sint16x2,
+        (Int16, false, 4) => F::Sint16x4,
+        (Int16, true, 1) => F::Snorm16,
+        (Int16, true, 2) => F::Snorm16x2,
+        (Int16, true, 4) => F::Snorm16x4,
+        (UInt16, false, 1) => F::Uint16,
+        (UInt16, false, 2) => F::Uint16x2,
+        (UInt16, false, 4) => F::Uint16x4,
+        (UInt16, true, 1) => F::Unorm16,
+        (UInt16, true, 2) => F::Unorm16x2,
+        (UInt16, true, 4) => F::Unorm16x4,
+        (Int32, false, 1) => F::Sint32,
+        (Int32, false, 2) => F::Sint32x2,
+        (Int32, false, 3) => F::Sint32x3,
+        (Int32, false, 4) => F::Sint32x4,
+        (UInt32, false, 1) => F::Uint32,
+        (UInt32, false, 2) => F::Uint32x2,
+        (UInt32, false, 3) => F::Uint32x3,
+        (UInt32, false, 4) => F::Uint32x4,
+        (Float32, _, 1) => F::Float32,
+        (Float32, _, 2) => F::Float32x2,
+        (Float32, _, 3) => F::Float32x3,
+        (Float32, _, 4) => F::Float32x4,
+        (Float64, _, 1) => F::Float64,
+        (Float64, _, 2) => F::Float64x2,
+        (Float64, _, 3) => F::Float64x3,
+        (Float64, _, 4) => F::Float64x4,
+        _ => return None,
+    };
+    Some(format)
+}
+
+impl AttributeLayout {
+    pub fn vertex_format(&self) -> Option<VertexFormat> {
+        vertex_format(self.data_type, self.component_count as u32, self.normalized)
+    }
+}
+
+impl From<IndexFormat> for ::wgpu::IndexFormat {
+    fn from(format: IndexFormat) -> Self {
+        match format {
+            IndexFormat::U16 => ::wgpu::IndexFormat::Uint16,
+            IndexFormat::U32 => ::wgpu::IndexFormat::Uint32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WgpuLayoutError {
+    /// wgpu has no vertex format for the attribute, see [`vertex_format`].
+    UnsupportedFormat { attribute: usize },
+    /// A planar attribute starts at or has a stride that isn't a multiple of 4; decode
+    /// with [`VertexLayout::Interleaved`] instead.
+    Misaligned { attribute: usize },
+}
+
+impl fmt::Display for WgpuLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WgpuLayoutError::UnsupportedFormat { attribute } => {
+                write!(f, "attribute {attribute} has no wgpu vertex format")
+            }
+            WgpuLayoutError::Misaligned { attribute } => {
+                write!(f, "attribute {attribute} is not 4 byte aligned")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WgpuLayoutError {}
+
+/// One vertex buffer: the slice of the decoded buffer to bind and its attributes.
+#[derive(Debug, Clone)]
+pub struct WgpuVertexBuffer {
+    pub byte_range: Range<usize>,
+    pub array_stride: u64,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+/// The vertex buffers of a decoded buffer, a single one for interleaved output and one per
+/// attribute for planar output. Shader locations follow the attribute order.
+#[derive(Debug, Clone)]
+pub struct WgpuVertexBuffers {
+    buffers: Vec<WgpuVertexBuffer>,
+}
+
+impl WgpuVertexBuffers {
+    pub fn buffers(&self) -> &[WgpuVertexBuffer] {
+        &self.buffers
+    }
+
+    /// Layouts for `wgpu::VertexState::buffers`, in the order of [`Self::buffers`].
+    pub fn layouts(&self) -> Vec<VertexBufferLayout<'_>> {
+        self.buffers
+            .iter()
+            .map(|buffer| VertexBufferLayout {
+                array_stride: buffer.array_stride,
+                step_mode: VertexStepMode::Vertex,
+                attributes: &buffer.attributes,
+            })
+            .collect()
+    }
+}
+
+impl BufferLayout {
+    pub fn index_format_wgpu(&self) -> ::wgpu::IndexFormat {
+        self.indices.format.into()
+    }
+
+    pub fn wgpu_vertex_buffers(&self) -> Result<WgpuVertexBuffers, WgpuLayoutError> {
+        let mut attributes = Vec::with_capacity(self.attributes.len());
+        for (index, attr) in self.attributes.iter().enumerate() {
+            let format = attr
+                .vertex_format()
+                .ok_or(WgpuLayoutError::UnsupportedFormat { attribute: index })?;
+            if attr.byte_range.start % VERTEX_ALIGNMENT != 0 || attr.stride % VERTEX_ALIGNMENT != 0
+            {
+                return Err(WgpuLayoutError::Misaligned { attribute: index });
+            }
+            attributes.push(VertexAttribute {
+                format,
+                offset: (attr.byte_range.start - self.vertex_range.start) as u64,
+                shader_location: index as u32,
+            });
+        }
+
+        let buffers = match self.vertex_layout {
+            VertexLayout::Interleaved => vec![WgpuVertexBuffer {
+                byte_range: self.vertex_range.clone(),
+                array_stride: self.attributes.first().map_or(0, |attr| attr.stride) as u64,
+                attributes,
+            }],
+            VertexLayout::Planar => self
+                .attributes
+                .iter()
+                .zip(attributes)
+                .map(|(attr, attribute)| WgpuVertexBuffer {
+                    byte_range: attr.byte_range.clone(),
+                    array_stride: attr.stride as u64,
+                    attributes: vec![VertexAttribute {
+                        offset: 0,
+                        ..attribute
+                    }],
+                })
+                .collect(),
+        };
+        Ok(WgpuVertexBuffers { buffers })
+    }
+}